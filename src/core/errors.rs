@@ -1,78 +1,314 @@
 #![allow(unused)]
 
-use std::fmt;
+use std::{error::Error, fmt};
 
-use crate::{Command, Event};
+use crate::{Command, Event, GroupPolicy};
 
 use super::EventConfig;
 
-#[derive(Debug, Clone)]
-pub enum CmderError {
-    MissingRequiredArgument(Vec<String>), // exit code 5
-    OptionMissingArgument(Vec<String>),   // exit code 10
-    UnknownCommand(String),               // exit code 15
-    UnknownOption(String),                // exit code 20
-    UnresolvedArgument(Vec<String>),      // exit code 25
+/// The error type returned by the parser. Every error carries the event emitted when it occurs, a descriptive message, the values that caused the error and the code the program exits with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CmderError {
+    pub(crate) kind: Event,
+    pub(crate) message: String,
+    pub(crate) args: Vec<String>,
+    pub(crate) exit_code: usize,
+    pub(crate) context: Box<ErrorContext>,
 }
 
-// #[derive(Debug, Clone)]
-// pub struct CmderErrorr<'err> {
-//     pub(crate) kind: Event,
-//     pub(crate) message: String,
-//     pub(crate) help: &'err str,
-//     pub(crate) args: Vec<String>,
-//     pub(crate) exit_code: usize,
-//     pub(crate) matched_cmd: Option<&'err Command<'err>>,
-// }
+// The values locating the error, kept behind a box so that results carrying an error stay small
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct ErrorContext {
+    pub(crate) help: Option<String>,
+    pub(crate) token: Option<String>,
+    pub(crate) matched_cmd: Option<String>,
+    pub(crate) suggestions: Vec<String>,
+}
 
 pub type CmderResult<T, E = CmderError> = Result<T, E>;
 
-// impl<'e> Into<EventConfig<'e>> for CmderErrorr<'e> {
-//     fn into(self) -> EventConfig<'e> {
-//         EventConfig::new(self.matched_cmd.unwrap())
-//             .info(self.help)
-//             .exit_code(self.exit_code)
-//             .error_str(self.message)
-//             .arg_c(self.args.len())
-//             .args(self.args)
-//     }
-// }
+impl CmderError {
+    pub(crate) fn new(kind: Event, message: String, args: Vec<String>) -> Self {
+        use Event::*;
+        let exit_code = match kind {
+            MissingRequiredArgument => 5,
+            OptionMissingArgument => 10,
+            UnknownCommand => 15,
+            UnknownOption => 20,
+            UnresolvedArgument => 25,
+            InvalidArgumentValue => 30,
+            TooManyOccurrences => 35,
+            MissingRequiredOption => 40,
+            ArgumentConflict => 45,
+            MissingDependency => 50,
+            ArgGroupViolation => 55,
+            WrongNumberOfValues => 60,
+            AmbiguousArgument => 65,
+            OutputHelp | OutputVersion | DeprecatedAlias => 0,
+        };
+
+        Self {
+            kind,
+            message,
+            args,
+            exit_code,
+            context: Box::default(),
+        }
+    }
+
+    pub(crate) fn missing_required_argument(args: Vec<String>) -> Self {
+        let arg_string = get_vector_string(&args);
+        let message = format!("Missing the following required argument(s): {arg_string}");
+        Self::new(Event::MissingRequiredArgument, message, args)
+    }
+
+    pub(crate) fn option_missing_argument(arg: &str, opt: &str) -> Self {
+        let message = format!("Missing required argument(s): `{arg}` for option: `{opt}`");
+        Self::new(
+            Event::OptionMissingArgument,
+            message,
+            vec![arg.into(), opt.into()],
+        )
+        .token(opt)
+    }
+
+    pub(crate) fn unknown_command(cmd: &str) -> Self {
+        let message = format!("Could not find command: `{cmd}`");
+        Self::new(Event::UnknownCommand, message, vec![cmd.into()]).token(cmd)
+    }
+
+    pub(crate) fn unknown_option(opt: &str) -> Self {
+        let message = format!("You have passed an unknown option: `{opt}`");
+        Self::new(Event::UnknownOption, message, vec![opt.into()]).token(opt)
+    }
+
+    pub(crate) fn unresolved_argument(vals: Vec<String>) -> Self {
+        let arg_string = get_vector_string(&vals);
+        let message = format!("Could not resolve the following argument(s): {arg_string}");
+        let token = vals[0].clone();
+        Self::new(Event::UnresolvedArgument, message, vals).token(&token)
+    }
+
+    pub(crate) fn invalid_argument_value(name: &str, val: &str, reason: &str) -> Self {
+        let message = format!("Invalid value `{val}` for argument `{name}`: {reason}");
+        let args = vec![name.into(), val.into(), reason.into()];
+        Self::new(Event::InvalidArgumentValue, message, args).token(val)
+    }
+
+    pub(crate) fn too_many_occurrences(flag: &str, max: usize) -> Self {
+        let message = format!("The flag `{flag}` cannot be passed more than {max} time(s)");
+        Self::new(
+            Event::TooManyOccurrences,
+            message,
+            vec![flag.into(), max.to_string()],
+        )
+        .token(flag)
+    }
+
+    pub(crate) fn missing_required_option(opts: Vec<String>) -> Self {
+        let opt_string = get_vector_string(&opts);
+        let message = format!("Missing the following required option(s): {opt_string}");
+        Self::new(Event::MissingRequiredOption, message, opts)
+    }
+
+    pub(crate) fn conditionally_required_option(opt: &str, rule: &str) -> Self {
+        let message = format!("Missing the required option `{opt}`: {rule}");
+        Self::new(Event::MissingRequiredOption, message, vec![opt.into()]).help(rule)
+    }
+
+    pub(crate) fn conditionally_required_argument(arg: &str, rule: &str) -> Self {
+        let message = format!("Missing the required argument `{arg}`: {rule}");
+        Self::new(Event::MissingRequiredArgument, message, vec![arg.into()]).help(rule)
+    }
+
+    pub(crate) fn argument_conflict(arg: &str, other: &str) -> Self {
+        let message = format!("The argument `{arg}` cannot be used together with `{other}`");
+        Self::new(
+            Event::ArgumentConflict,
+            message,
+            vec![arg.into(), other.into()],
+        )
+        .token(arg)
+    }
+
+    pub(crate) fn missing_dependency(arg: &str, required: &str) -> Self {
+        let message = format!("The argument `{arg}` requires `{required}` to also be passed");
+        Self::new(
+            Event::MissingDependency,
+            message,
+            vec![arg.into(), required.into()],
+        )
+        .token(arg)
+    }
+
+    pub(crate) fn arg_group_violation(
+        group: &str,
+        policy: GroupPolicy,
+        members: Vec<String>,
+    ) -> Self {
+        let member_string = get_vector_string(&members);
+        let requirement = match policy {
+            GroupPolicy::ExactlyOne => "exactly one of",
+            GroupPolicy::AtLeastOne => "at least one of",
+            GroupPolicy::AtMostOne => "at most one of",
+        };
+        let message = format!(
+            "Expected {requirement} the following argument(s) for group `{group}`: {member_string}"
+        );
+
+        let mut args = vec![group.to_string()];
+        args.extend(members);
+        Self::new(Event::ArgGroupViolation, message, args)
+    }
+
+    pub(crate) fn wrong_number_of_values(
+        name: &str,
+        range: (usize, Option<usize>),
+        got: usize,
+    ) -> Self {
+        let expected = match range {
+            (min, Some(max)) if min == max => min.to_string(),
+            (min, None) => format!("at least {min}"),
+            (0, Some(max)) => format!("at most {max}"),
+            (min, Some(max)) => format!("{min} to {max}"),
+        };
+        let message = format!("Expected {expected} value(s) for `{name}`, got {got}");
+
+        Self::new(
+            Event::WrongNumberOfValues,
+            message,
+            vec![name.into(), expected, got.to_string()],
+        )
+        .token(name)
+    }
+
+    pub(crate) fn ambiguous_argument(arg: &str, candidates: Vec<String>) -> Self {
+        let candidate_string = get_vector_string(&candidates);
+        let message =
+            format!("The argument `{arg}` is ambiguous, it could match: {candidate_string}");
+
+        Self::new(Event::AmbiguousArgument, message, vec![arg.into()])
+            .token(arg)
+            .suggestions(candidates)
+    }
+
+    pub(crate) fn token(mut self, val: &str) -> Self {
+        self.context.token = Some(val.into());
+        self
+    }
+
+    pub(crate) fn help(mut self, val: &str) -> Self {
+        self.context.help = Some(val.into());
+        self
+    }
+
+    pub(crate) fn matched_cmd(mut self, path: &str) -> Self {
+        if self.context.matched_cmd.is_none() {
+            self.context.matched_cmd = Some(path.into());
+        }
+        self
+    }
+
+    pub(crate) fn suggestions(mut self, vals: Vec<String>) -> Self {
+        self.context.suggestions = vals;
+        self
+    }
+
+    // Attaches the values similar to the offending token, the closest one is also appended to the message, i.e "did you mean `container`?"
+    pub(crate) fn did_you_mean(mut self, vals: Vec<String>) -> Self {
+        if let Some(best) = vals.first() {
+            let hint = format!("did you mean `{best}`?");
+            self.message = format!("{}, {hint}", self.message);
+            self.context.help = Some(hint);
+            self.context.suggestions = vals;
+        }
+        self
+    }
+}
+
+// Getters for the error values
+impl CmderError {
+    /// Returns the kind of the error, which is the event emitted when it occurs
+    pub fn get_kind(&self) -> Event {
+        self.kind
+    }
+
+    /// Returns the error message
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns any additional help for resolving the error
+    pub fn get_help(&self) -> Option<&str> {
+        self.context.help.as_deref()
+    }
+
+    /// Returns the values that caused the error
+    pub fn get_args(&self) -> Vec<String> {
+        self.args.clone()
+    }
+
+    /// Returns the offending token from the command line, if any
+    pub fn get_token(&self) -> Option<&str> {
+        self.context.token.as_deref()
+    }
+
+    /// Returns the code the program exits with when the error occurs
+    pub fn get_exit_code(&self) -> usize {
+        self.exit_code
+    }
+
+    /// Returns the path of the command that was matched when the error occurred, i.e `docker image prune`
+    pub fn get_matched_cmd(&self) -> Option<&str> {
+        self.context.matched_cmd.as_deref()
+    }
+
+    /// Returns the values suggested in place of the offending token
+    pub fn get_suggestions(&self) -> &Vec<String> {
+        &self.context.suggestions
+    }
+
+    /// Converts the error into the config passed to the listeners of the error event
+    pub fn into_event_cfg<'a>(self, program: &'a Command<'a>) -> EventConfig<'a> {
+        let mut cfg = EventConfig::new(program)
+            .set_event(self.kind)
+            .exit_code(self.exit_code)
+            .args(self.args.clone())
+            .error_str(self.message.clone());
+
+        if let Some(cmd) = self.get_matched_cmd().and_then(|p| find_cmd(program, p)) {
+            cfg = cfg.set_matched_cmd(cmd);
+        }
+
+        cfg.error(self)
+    }
+}
+
+// Resolves a command from its path, skipping the name of the root command
+fn find_cmd<'a>(program: &'a Command<'a>, path: &str) -> Option<&'a Command<'a>> {
+    let mut cmd = program;
+
+    for name in path.split_whitespace().skip(1) {
+        cmd = cmd.find_subcommand(name)?;
+    }
+
+    Some(cmd)
+}
 
 impl From<CmderError> for String {
     fn from(err: CmderError) -> Self {
-        use CmderError::*;
-        match err {
-            MissingRequiredArgument(ref val) => {
-                let arg_string = get_vector_string(val);
-                format!("Missing the following required argument(s): {arg_string}")
-            }
-            OptionMissingArgument(ref args) => {
-                format!(
-                    "Missing required argument(s): `{}` for option: `{}`",
-                    args[0], args[1]
-                )
-            }
-            UnknownCommand(cmd) => {
-                format!("Could not find command: `{cmd}`")
-            }
-            UnknownOption(opt) => {
-                format!("You have passed an unknown option: `{opt}`")
-            }
-            UnresolvedArgument(ref vals) => {
-                let arg_string = get_vector_string(vals);
-                format!("Could not resolve the following argument(s): {arg_string}")
-            }
-        }
+        err.message
     }
 }
 
-impl<'a> fmt::Display for CmderError {
+impl fmt::Display for CmderError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
-        let val: String = self.to_owned().into();
-        f.write_str(&val)
+        f.write_str(&self.message)
     }
 }
 
+impl Error for CmderError {}
+
 fn get_vector_string(args: &Vec<String>) -> String {
     let mut res = String::new();
     for a in args {
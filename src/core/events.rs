@@ -1,7 +1,7 @@
 #![allow(unused)]
 use std::{collections::HashMap, fmt::Debug};
 
-use super::program::Command;
+use super::{errors::CmderError, program::Command};
 
 /// The event config struct defines the structure of the data passed to a listener to a particular event. Whenever an event occurs, its config is generated depending on the context. All its members are private but has numerous getters to access the fields data.
 #[derive(Clone, Debug)]
@@ -14,6 +14,7 @@ pub struct EventConfig<'e> {
     pub(crate) matched_cmd: Option<&'e Command<'e>>,
     pub(crate) additional_info: &'e str,
     pub(crate) program_ref: &'e Command<'e>,
+    pub(crate) error: Option<CmderError>,
 }
 
 impl<'a> EventConfig<'a> {
@@ -41,6 +42,11 @@ impl<'a> EventConfig<'a> {
     pub fn get_matched_cmd(&self) -> Option<&Command<'a>> {
         self.matched_cmd
     }
+
+    /// Returns the error that caused the event, if the event is an error event
+    pub fn get_error(&self) -> Option<&CmderError> {
+        self.error.as_ref()
+    }
 }
 
 impl<'a> EventConfig<'a> {
@@ -54,8 +60,45 @@ impl<'a> EventConfig<'a> {
             exit_code: 0,
             matched_cmd: None,
             program_ref: cmd,
+            error: None,
         }
     }
+
+    pub(crate) fn set_event(mut self, event: Event) -> Self {
+        self.event_type = event;
+        self
+    }
+
+    pub(crate) fn set_matched_cmd(mut self, cmd: &'a Command<'a>) -> Self {
+        self.matched_cmd = Some(cmd);
+        self
+    }
+
+    pub(crate) fn args(mut self, args: Vec<String>) -> Self {
+        self.arg_count = args.len();
+        self.args = args;
+        self
+    }
+
+    pub(crate) fn error_str(mut self, val: String) -> Self {
+        self.error_string = val;
+        self
+    }
+
+    pub(crate) fn exit_code(mut self, code: usize) -> Self {
+        self.exit_code = code;
+        self
+    }
+
+    pub(crate) fn info(mut self, info: &'a str) -> Self {
+        self.additional_info = info;
+        self
+    }
+
+    pub(crate) fn error(mut self, err: CmderError) -> Self {
+        self.error = Some(err);
+        self
+    }
 }
 
 pub type EventCallback = fn(EventConfig) -> ();
@@ -82,6 +125,7 @@ impl Debug for EventEmitter {
 #[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
 pub enum Event {
     MissingRequiredArgument,
+    OptionMissingArgument,
     OutputHelp,
     OutputVersion,
     UnknownCommand,
@@ -89,12 +133,20 @@ pub enum Event {
     UnresolvedArgument,
     InvalidArgumentValue,
     MissingRequiredOption,
+    TooManyOccurrences,
+    ArgumentConflict,
+    MissingDependency,
+    ArgGroupViolation,
+    WrongNumberOfValues,
+    AmbiguousArgument,
+    DeprecatedAlias,
 }
 
 fn get_events_slice() -> Vec<Event> {
     use Event::*;
     vec![
         MissingRequiredArgument,
+        OptionMissingArgument,
         OutputHelp,
         OutputVersion,
         UnknownCommand,
@@ -102,6 +154,13 @@ fn get_events_slice() -> Vec<Event> {
         UnresolvedArgument,
         InvalidArgumentValue,
         MissingRequiredOption,
+        TooManyOccurrences,
+        ArgumentConflict,
+        MissingDependency,
+        ArgGroupViolation,
+        WrongNumberOfValues,
+        AmbiguousArgument,
+        DeprecatedAlias,
     ]
 }
 
@@ -139,13 +198,16 @@ impl EventEmitter {
 
         if let Some(lstnrs) = self.listeners.get(&event) {
             let mut lstnrs = lstnrs.clone();
-            lstnrs.sort_by(|a, b| a.index.cmp(&b.index));
+            lstnrs.sort_by_key(|l| l.index);
 
             for (lstnr) in lstnrs {
                 (lstnr.cb)(cfg.clone());
             }
 
-            std::process::exit(cfg.get_exit_code() as i32);
+            // Warnings do not interrupt the program
+            if event != Event::DeprecatedAlias {
+                std::process::exit(cfg.get_exit_code() as i32);
+            }
         }
     }
 
@@ -171,7 +233,7 @@ impl EventEmitter {
         use Event::*;
         for event in get_events_slice() {
             // Ignore events that aren't errors
-            if event == OutputHelp || event == OutputVersion {
+            if event == OutputHelp || event == OutputVersion || event == DeprecatedAlias {
                 continue;
             } else {
                 self.on(event, cb, pstn)
@@ -2,17 +2,14 @@
 use std::{env, fmt::Debug, path::PathBuf, rc::Rc};
 
 use crate::{
-    core::errors::CmderError,
+    core::errors::{CmderError, CmderResult},
     parse::{
-        flags::new_flag,
-        matches::ParserMatches,
-        options::new_option,
-        parser::{NewParser, Parser},
-        Argument,
+        flags::new_flag, matches::ParserMatches, matching::MatchPolicy, options::new_option,
+        Argument, Parser,
     },
     ui::{formatter::FormatGenerator, themes::get_predefined_theme},
     utils::{self, HelpWriter},
-    Event, Pattern, PredefinedTheme, Theme,
+    ArgGroup, Event, Pattern, PredefinedTheme, Theme,
 };
 
 use super::events::{EventCallback, EventListener};
@@ -54,12 +51,16 @@ pub struct Command<'p> {
     theme: Theme,
     is_root: bool,
     pattern: Pattern,
-    alias: Option<&'p str>,
+    aliases: Vec<&'p str>,
+    hidden_aliases: Vec<&'p str>,
+    // Deprecated aliases along with the warning emitted when they are used
+    deprecated_aliases: Vec<(&'p str, &'p str)>,
     author: Option<&'p str>,
     version: Option<&'p str>,
     arguments: Vec<Argument>,
     flags: Vec<CmderFlag>,
     options: Vec<CmderOption>,
+    groups: Vec<ArgGroup>,
     description: Option<&'p str>,
     more_info: Option<&'p str>,
     usage_str: Option<&'p str>,
@@ -82,7 +83,7 @@ impl<'d> Debug for Command<'d> {
             subcmds: {:#?},
             ",
             self.name,
-            self.alias.unwrap_or(""),
+            self.aliases.join(", "),
             self.arguments,
             self.flags,
             self.options,
@@ -96,13 +97,16 @@ impl<'p> Command<'p> {
     pub fn new(name: &'p str) -> Self {
         Self {
             name: name.to_string(),
-            alias: None,
+            aliases: vec![],
+            hidden_aliases: vec![],
+            deprecated_aliases: vec![],
             arguments: vec![],
             description: None,
             flags: vec![CmderFlag::new("help")
                 .short('h')
                 .help("Print out help information")],
             options: vec![],
+            groups: vec![],
             subcommands: vec![],
             callback: None,
             parent: None,
@@ -145,9 +149,52 @@ impl<'p> Command<'p> {
         self.name.as_str()
     }
 
-    /// A getter for the command alias or empty value if none is found
+    /// A getter for the first visible alias of the command or empty value if none is found
     pub fn get_alias(&self) -> &str {
-        self.alias.unwrap_or("")
+        self.aliases.first().copied().unwrap_or("")
+    }
+
+    /// Returns the visible aliases of the command
+    pub fn get_aliases(&self) -> &Vec<&'p str> {
+        &self.aliases
+    }
+
+    /// Returns the aliases of the command that are never shown in help
+    pub fn get_hidden_aliases(&self) -> &Vec<&'p str> {
+        &self.hidden_aliases
+    }
+
+    /// Returns the deprecated aliases of the command along with their warnings
+    pub fn get_deprecated_aliases(&self) -> &Vec<(&'p str, &'p str)> {
+        &self.deprecated_aliases
+    }
+
+    // Checks whether the value matches any of the visible, hidden or deprecated aliases of the command under the given policy
+    fn has_alias(&self, val: &str, policy: MatchPolicy) -> bool {
+        let deprecated = self.deprecated_aliases.iter().map(|(a, _)| a);
+
+        self.aliases
+            .iter()
+            .chain(self.hidden_aliases.iter())
+            .chain(deprecated)
+            .any(|a| policy.matches(a, val))
+    }
+
+    // Returns the warning to emit when the command is invoked through the given value, if it is a deprecated alias
+    pub(crate) fn get_deprecation_warning(&self, val: &str, policy: MatchPolicy) -> Option<String> {
+        let (alias, warning) = self
+            .deprecated_aliases
+            .iter()
+            .find(|(a, _)| policy.matches(a, val))?;
+
+        if warning.is_empty() {
+            Some(format!(
+                "The alias `{alias}` is deprecated, use `{}` instead",
+                self.get_name()
+            ))
+        } else {
+            Some(warning.to_string())
+        }
     }
 
     /// Returns a reference to a vector containing all the flags of a given command
@@ -165,6 +212,28 @@ impl<'p> Command<'p> {
         &self.options
     }
 
+    pub(crate) fn get_setting(&self, setting: Setting) -> bool {
+        self.settings.get(setting)
+    }
+
+    // Checks whether a setting is enabled on the command or on any of its parents, up to the program
+    pub(crate) fn get_inherited_setting(&self, setting: Setting) -> bool {
+        let mut parent = self.get_parent();
+        let mut enabled = self.get_setting(setting.clone());
+
+        while let (false, Some(p)) = (enabled, parent) {
+            enabled = p.get_setting(setting.clone());
+            parent = p.get_parent();
+        }
+
+        enabled
+    }
+
+    /// Returns the argument groups configured on the command
+    pub fn get_groups(&self) -> &Vec<ArgGroup> {
+        &self.groups
+    }
+
     /// Returns borrowed vectot with command arguments
     pub fn get_arguments(&self) -> &Vec<Argument> {
         &self.arguments
@@ -229,9 +298,38 @@ impl<'p> Command<'p> {
         self
     }
 
-    /// Sets the alias of a given command
+    /// Adds an alias to a given command. A command can have any number of aliases, which are listed in help when the `ShowCommandAliases` setting is enabled
     pub fn alias(&mut self, val: &'p str) -> &mut Self {
-        self.alias = Some(val);
+        self.aliases.push(val);
+        self
+    }
+
+    /// Adds several visible aliases to a given command at once
+    /// ```
+    /// use cmder::{Program};
+    ///
+    /// let mut program = Program::new();
+    ///
+    /// program
+    ///     .subcommand("install")
+    ///     .aliases(vec!["i", "add"])
+    ///     .hidden_alias("isntall")
+    ///     .deprecated_alias("get", "`get` will be removed in the next release, use `install` instead");
+    /// ```
+    pub fn aliases(&mut self, vals: Vec<&'p str>) -> &mut Self {
+        self.aliases.extend(vals);
+        self
+    }
+
+    /// Adds an alias that resolves to the command but is never shown in help
+    pub fn hidden_alias(&mut self, val: &'p str) -> &mut Self {
+        self.hidden_aliases.push(val);
+        self
+    }
+
+    /// Adds a hidden alias that still resolves to the command but emits the `DeprecatedAlias` event with the given warning whenever it is used. An empty warning falls back to a default message pointing to the command name
+    pub fn deprecated_alias(&mut self, val: &'p str, warning: &'p str) -> &mut Self {
+        self.deprecated_aliases.push((val, warning));
         self
     }
 
@@ -327,7 +425,7 @@ impl<'p> Command<'p> {
     /// Command::new("test").add_flag(
     ///   CmderFlag::new("version")
     ///     .help("Version flag")
-    ///     .short('-v')
+    ///     .short('v')
     /// );
     /// ```
     pub fn add_flag(&mut self, flag: CmderFlag) -> &mut Self {
@@ -371,18 +469,25 @@ impl<'p> Command<'p> {
         self
     }
 
-    /// Adds a new option to a command. Accepts the option syntax value and the help string
+    /// Adds a new option to a command. Accepts the option syntax value and the help string. If no arguments are declared in the syntax value, a flag is added instead
     ///
     /// ```
     /// use cmder::{Command};
     ///
     /// Command::new("empty")
     ///     .option("-p --port <port-no>", "The port to use")
-    ///     .option("-c --count <number>", "Some count value");
+    ///     .option("-c --count <number>", "Some count value")
+    ///     .option("-q --quiet", "A flag that takes no value");
     ///
     /// ```
     pub fn option(&mut self, val: &'p str, help: &'static str) -> &mut Self {
-        self.add_option(new_option(val, help, false));
+        let opt = new_option(val, help, false);
+
+        if opt.arguments.is_empty() {
+            self.add_flag(new_flag(val, help));
+        } else {
+            self.add_option(opt);
+        }
         self
     }
 
@@ -399,13 +504,41 @@ impl<'p> Command<'p> {
         self
     }
 
+    /// Adds an argument group to the command. The members of the group are validated together according to the group policy once parsing is done
+    /// ```
+    /// use cmder::{ArgGroup, Command, GroupPolicy};
+    ///
+    /// Command::new("export")
+    ///     .flag("--json", "Output json")
+    ///     .flag("--table", "Output a table")
+    ///     .add_group(
+    ///         ArgGroup::new("format")
+    ///             .members(vec!["--json", "--table"])
+    ///             .policy(GroupPolicy::AtMostOne),
+    ///     );
+    /// ```
+    pub fn add_group(&mut self, group: ArgGroup) -> &mut Self {
+        if !self.groups.contains(&group) {
+            self.groups.push(group);
+        }
+        self
+    }
+
     /********************************* Utility Methods ***********************************/
 
-    /// A utility method used to try and find a subcommand within a command.
+    /// A utility method used to try and find a subcommand within a command. The name and aliases are matched according to the `IgnoreCase` and `IgnoreSeparators` settings of the command or of any of its parents
     pub fn find_subcommand(&self, val: &str) -> Option<&Command<'_>> {
+        self.find_subcommand_with(val, MatchPolicy::from_cmd(self))
+    }
+
+    pub(crate) fn find_subcommand_with(
+        &self,
+        val: &str,
+        policy: MatchPolicy,
+    ) -> Option<&Command<'_>> {
         self.subcommands
             .iter()
-            .find(|c| c.get_name() == val || c.get_alias() == val)
+            .find(|c| policy.matches(c.get_name(), val) || c.has_alias(val, policy))
     }
 
     fn _set_bin_name(&mut self, val: &str) {
@@ -498,7 +631,7 @@ impl<'p> Command<'p> {
     /// let mut p = Program::new();
     ///
     /// p.set(Setting::ShowHelpOnAllErrors, true);
-    /// p.set(Setting::HideCommandAliases, false);
+    /// p.set(Setting::ShowCommandAliases, false);
     /// // other settings...
     /// ```
     pub fn set(&mut self, setting: Setting, val: bool) {
@@ -514,7 +647,7 @@ impl<'p> Command<'p> {
     /// A method to configure the theme to be used by the program. You can also use the method to define your own custom theme.
     ///
     /// ```
-    /// use cmder::{Program, Theme};
+    /// use cmder::{Program, Theme, Color::*};
     ///
     /// let mut program = Program::new();
     ///
@@ -545,22 +678,20 @@ impl<'p> Command<'p> {
     }
 
     fn __parse(&'p mut self, args: Vec<String>) {
-        self._set_bin_name(&args[0]);
-
-        // TODO: Rewrite this functionality
-        self.__init(); // performance dip here
-
-        {
-            let mut parser = NewParser::new(self);
-            parser.parse(["hey", "you"])
-        }
-
-        let mut parser = Parser::new(self);
+        self.__prepare(&args);
 
-        match parser.parse(args[1..].to_vec()) {
+        match self.__parse_args(&args) {
             Ok(matches) => {
                 self._handle_root_flags(&matches);
 
+                for (alias, name, warning) in &matches.deprecated_aliases {
+                    let cfg = EventConfig::new(self)
+                        .set_event(Event::DeprecatedAlias)
+                        .args(vec![alias.clone(), name.clone()])
+                        .error_str(warning.clone());
+                    self.emit(cfg);
+                }
+
                 if let Some(cmd) = matches.get_matched_cmd() {
                     if let Some(cb) = cmd.callback {
                         // if matches.get_raw_args_count() <= 1
@@ -575,17 +706,73 @@ impl<'p> Command<'p> {
                     }
                 }
             }
-            Err(e) => {
-                // FIXME: No clones
-                // TODO: Impl into eventcfg from cmdererror
+            Err(e) => self.emit(e.into_event_cfg(self)),
+        }
+    }
+
+    fn __prepare(&mut self, args: &[String]) {
+        if let Some(bin) = args.first() {
+            self._set_bin_name(bin);
+        }
+
+        // TODO: Rewrite this functionality
+        self.__init(); // performance dip here
+        self.propagate_globals();
+    }
+
+    // Copies the global flags and options of the command into its subcommands at every depth, so that they can be passed both before and after a subcommand. Flags and options defined by a subcommand itself take precedence over global ones with the same long or short name.
+    pub(crate) fn propagate_globals(&mut self) {
+        let flags: Vec<CmderFlag> = self.flags.iter().filter(|f| f.is_global).cloned().collect();
+        let options: Vec<CmderOption> = self
+            .options
+            .iter()
+            .filter(|o| o.is_global)
+            .cloned()
+            .collect();
+
+        for sub_cmd in &mut self.subcommands {
+            for flag in &flags {
+                if !sub_cmd.defines_name(&flag.long, &flag.short) {
+                    sub_cmd.flags.push(flag.clone());
+                }
+            }
 
-                // TODO: Implement new Error handling
+            for opt in &options {
+                if !sub_cmd.defines_name(&opt.long, &opt.short) {
+                    sub_cmd.options.push(opt.clone());
+                }
             }
+
+            sub_cmd.propagate_globals();
         }
     }
 
+    // Checks whether the command has a flag or option with the given long or short name
+    fn defines_name(&self, long: &str, short: &str) -> bool {
+        let clashes = |l: &str, s: &str| l == long || (!short.is_empty() && s == short);
+
+        self.flags.iter().any(|f| clashes(&f.long, &f.short))
+            || self.options.iter().any(|o| clashes(&o.long, &o.short))
+    }
+
+    fn __parse_args(&'p self, args: &[String]) -> CmderResult<ParserMatches<'p>> {
+        let mut parser = Parser::new(self);
+        parser.parse(args.iter().skip(1).cloned().collect())
+    }
+
+    // Parses the args without ever emitting events, including from the getters of the matches returned
+    fn __try_parse_args(&'p self, args: &[String]) -> CmderResult<ParserMatches<'p>> {
+        self.__parse_args(args).map(|mut matches| {
+            matches.is_silent = true;
+            matches
+        })
+    }
+
     fn __init(&mut self) {
-        if !self.subcommands.is_empty() && self.settings.get(Setting::AutoIncludeHelpSubcommand) {
+        if !self.subcommands.is_empty()
+            && self.settings.get(Setting::AutoIncludeHelpSubcommand)
+            && self.find_subcommand("help").is_none()
+        {
             // Add help subcommand
             self.subcommand("help")
                 .argument("<SUB-COMMAND>", "The subcommand to print out help info for")
@@ -628,11 +815,24 @@ impl<'p> Command<'p> {
                             if !error.is_empty() {
                                 eprintln!("Error: {error}");
                             }
+
+                            if cfg.get_program().settings.get(Setting::ShowHelpOnAllErrors) {
+                                cfg.get_matched_cmd()
+                                    .unwrap_or_else(|| cfg.get_program())
+                                    .output_help();
+                            }
                         },
                         -4,
                     );
                 }
 
+                // Default behavior for deprecated aliases is to print the warning and carry on
+                emitter.on(
+                    DeprecatedAlias,
+                    |cfg| eprintln!("Warning: {}", cfg.get_error_str()),
+                    -4,
+                );
+
                 // Register default output version listener
                 emitter.on(
                     OutputVersion,
@@ -667,6 +867,31 @@ impl<'p> Command<'p> {
         self.__parse(args);
     }
 
+    /// Similar to the `parse` method, except that it neither invokes the action of the matched command nor emits any events. The parser matches or the error encountered are returned instead, and the program never exits or prints anything
+    pub fn try_parse(&'p mut self) -> CmderResult<ParserMatches<'p>> {
+        let args = env::args().collect::<Vec<_>>();
+        self.__prepare(&args);
+        self.__try_parse_args(&args)
+    }
+
+    /// The non-exiting version of `parse_from`. The first value is treated as the binary name
+    ///
+    /// ```
+    /// use cmder::{Event, Program};
+    ///
+    /// let mut program = Program::new();
+    /// program.argument("<name>", "The name to greet");
+    ///
+    /// let err = program.try_parse_from(vec!["greet"]).unwrap_err();
+    /// assert_eq!(err.get_kind(), Event::MissingRequiredArgument);
+    /// assert_eq!(err.get_args(), vec!["<name>"]);
+    /// ```
+    pub fn try_parse_from(&'p mut self, list: Vec<&str>) -> CmderResult<ParserMatches<'p>> {
+        let args = list.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        self.__prepare(&args);
+        self.__try_parse_args(&args)
+    }
+
     // Others
 
     /// Prints out help information for a command
@@ -709,11 +934,7 @@ impl<'f> FormatGenerator for Command<'f> {
                 let mut leading = base.replace("{{name}}", self.get_name());
                 let mut floating = String::from("");
 
-                if let Some(alias) = self.alias {
-                    leading = leading.replace("{{alias}}", alias)
-                } else {
-                    leading = leading.replace("{{alias}}", "")
-                }
+                leading = leading.replace("{{alias}}", &self.aliases.join(", "));
 
                 if base.contains("{{args}}") && !self.get_arguments().is_empty() {
                     let mut value = String::new();
@@ -789,4 +1010,41 @@ mod tests {
                 .help("Print out help information")]
         );
     }
+
+    #[test]
+    fn test_cmd_aliases() {
+        let mut program = Program::new();
+        program
+            .subcommand("install")
+            .description("Install a package")
+            .alias("i")
+            .aliases(vec!["add"])
+            .hidden_alias("isntall")
+            .deprecated_alias("get", "")
+            .deprecated_alias("fetch", "`fetch` is going away");
+
+        for name in ["install", "i", "add", "isntall", "get", "fetch"] {
+            let cmd = program.find_subcommand(name).unwrap();
+            assert_eq!(cmd.get_name(), "install");
+        }
+
+        let cmd = program.find_subcommand("i").unwrap();
+        assert_eq!(cmd.get_alias(), "i");
+        assert_eq!(cmd.get_aliases(), &vec!["i", "add"]);
+        assert_eq!(cmd.get_hidden_aliases(), &vec!["isntall"]);
+        assert_eq!(
+            cmd.get_deprecation_warning("i", MatchPolicy::default()),
+            None
+        );
+        assert_eq!(
+            cmd.get_deprecation_warning("get", MatchPolicy::default())
+                .unwrap(),
+            "The alias `get` is deprecated, use `install` instead"
+        );
+        assert_eq!(
+            cmd.get_deprecation_warning("fetch", MatchPolicy::default())
+                .unwrap(),
+            "`fetch` is going away"
+        );
+    }
 }
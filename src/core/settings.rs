@@ -20,6 +20,11 @@ impl Default for ProgramSettings {
         let mut values = HashMap::new();
 
         use Setting::*;
+        values.insert(AllowAbbreviations, false);
+        values.insert(AllowNegativeNumbers, false);
+        values.insert(IgnoreCase, false);
+        values.insert(IgnoreSeparators, false);
+        values.insert(PassThroughDoubleDash, false);
         values.insert(AutoIncludeHelpSubcommand, true);
         values.insert(IgnoreAllErrors, false);
         values.insert(OverrideAllDefaultListeners, false);
@@ -39,4 +44,14 @@ pub enum Setting {
     ShowCommandAliases,
     OverrideAllDefaultListeners,
     AutoIncludeHelpSubcommand,
+    /// Accepts values such as `-5` for options and arguments. Applies to the command it is set on and to all of its subcommands
+    AllowNegativeNumbers,
+    /// Treats `--` as a regular value. Applies to the command it is set on and to all of its subcommands
+    PassThroughDoubleDash,
+    /// Resolves unique prefixes of long flags, options and subcommands. Applies to the command it is set on and to all of its subcommands
+    AllowAbbreviations,
+    /// Matches names, aliases and valid values regardless of case. Applies to the command it is set on and to all of its subcommands
+    IgnoreCase,
+    /// Matches names, aliases and valid values regardless of `_`/`-` separators. Applies to the command it is set on and to all of its subcommands
+    IgnoreSeparators,
 }
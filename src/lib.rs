@@ -12,7 +12,7 @@
 //!
 //! The following is a full-fleged example on crate usage:
 //! ```
-//! use cmder::{Program, Event, Setting, Pattern, PredefinedThemes};
+//! use cmder::{Program, Event, Setting, PredefinedTheme};
 //!
 //! let mut program = Program::new();
 //!
@@ -43,10 +43,9 @@
 //! // ...
 //!
 //! // Program settings
-//! program.set(Setting::ShowHelpOnAllErrors(true));
-//! program.set(Setting::ChoosePredefinedTheme(PredefinedThemes::Colorful));
-//! program.set(Setting::SetProgramPattern(Pattern::Standard));
-//! program.set(Setting::OverrideAllDefaultListeners(true));
+//! program.set(Setting::ShowHelpOnAllErrors, true);
+//! program.set(Setting::OverrideAllDefaultListeners, true);
+//! program.use_predefined_theme(PredefinedTheme::Colorful);
 //!
 //! program.parse();
 //! ```
@@ -112,7 +111,9 @@ mod utils;
 /// The UI module houses the formatter module that is used to print to stdout and the themes module used to construct and define new themes.
 mod ui;
 
-pub use crate::core::{Command, Event, EventEmitter, Program, Setting};
-pub use parse::{Argument, CmderFlag, CmderOption, ParserMatches};
+pub use crate::core::{CmderError, CmderResult, Command, Event, EventEmitter, Program, Setting};
+pub use parse::{
+    ArgGroup, Argument, CmderFlag, CmderOption, GroupPolicy, ParserMatches, ValueForm,
+};
 pub use termcolor::Color;
 pub use ui::{CustomPattern, Designation, Formatter, Pattern, PredefinedTheme, Theme};
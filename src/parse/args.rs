@@ -1,7 +1,12 @@
+use crate::core::{CmderError, CmderResult};
 use crate::ui::formatter::FormatGenerator;
+use crate::utils::suggest;
+
+use super::{groups::describe_relations, matching::MatchPolicy};
 
 pub type ArgValidationFn = fn(String) -> Result<(), String>;
 
+#[allow(unpredictable_function_pointer_comparisons)]
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Argument {
     raw: String,
@@ -12,6 +17,16 @@ pub struct Argument {
     valid_values: Vec<String>,
     default_value: Option<String>,
     validation_fn: Option<ArgValidationFn>,
+    env: Option<String>,
+    conflicts_with: Vec<String>,
+    requires: Vec<String>,
+    groups: Vec<String>,
+    required_if: Vec<(String, String)>,
+    required_unless: Vec<String>,
+    default_if: Vec<(String, String, String)>,
+    min_values: Option<usize>,
+    max_values: Option<usize>,
+    allow_hyphen_values: bool,
 }
 
 impl Argument {
@@ -31,10 +46,7 @@ impl Argument {
             raw = val.into()
         };
 
-        name = name
-            .replace(delimiters[0], "")
-            .replace(delimiters[1], "")
-            .replace('-', "");
+        name = name.replace([delimiters[0], delimiters[1], '-'], "");
 
         if name.ends_with("...") {
             name = name.replace("...", "");
@@ -50,6 +62,16 @@ impl Argument {
             valid_values: vec![],
             default_value: None,
             validation_fn: None,
+            env: None,
+            conflicts_with: vec![],
+            requires: vec![],
+            groups: vec![],
+            required_if: vec![],
+            required_unless: vec![],
+            default_if: vec![],
+            min_values: None,
+            max_values: None,
+            allow_hyphen_values: false,
         }
     }
 
@@ -86,11 +108,79 @@ impl Argument {
         self
     }
 
+    /// Sets the exact number of values the argument takes, making it variadic if more than one value is expected
+    pub fn num_values(self, n: usize) -> Self {
+        self.min_values(n).max_values(n)
+    }
+
+    /// Sets the minimum number of values a variadic argument takes
+    pub fn min_values(mut self, n: usize) -> Self {
+        self.min_values = Some(n);
+        self.is_variadic = self.is_variadic || n > 1;
+        self
+    }
+
+    /// Sets the maximum number of values a variadic argument takes, any values past the maximum are left for the arguments that follow
+    pub fn max_values(mut self, n: usize) -> Self {
+        self.max_values = Some(n);
+        self.is_variadic = self.is_variadic || n > 1;
+        self
+    }
+
+    /// Allows values starting with a dash, i.e `-5` or `-x`, to be passed to the argument. Values matching a flag or option of the command are still parsed as such
+    pub fn allow_hyphen_values(mut self, val: bool) -> Self {
+        self.allow_hyphen_values = val;
+        self
+    }
+
     pub fn validate_with(mut self, validation_fn: ArgValidationFn) -> Self {
         self.validation_fn = Some(validation_fn);
         self
     }
 
+    /// Sets an environment variable to read the argument value from when no value is passed
+    pub fn env(mut self, val: &str) -> Self {
+        self.env = Some(val.into());
+        self
+    }
+
+    /// Marks the argument as conflicting with another flag, option or argument, the two cannot be passed together
+    pub fn conflicts_with(mut self, val: &str) -> Self {
+        self.conflicts_with.push(val.into());
+        self
+    }
+
+    /// Marks another flag, option or argument as required whenever the argument is passed
+    pub fn requires(mut self, val: &str) -> Self {
+        self.requires.push(val.into());
+        self
+    }
+
+    /// Adds the argument to the argument group with the given name
+    pub fn group(mut self, val: &str) -> Self {
+        self.groups.push(val.into());
+        self
+    }
+
+    /// Marks the argument as required whenever the given flag, option, option argument or argument has the given value. Flags have the value `true` when switched on and `false` when negated
+    pub fn required_if(mut self, id: &str, val: &str) -> Self {
+        self.required_if.push((id.into(), val.into()));
+        self
+    }
+
+    /// Marks the argument as required unless the given flag, option, option argument or argument is passed
+    pub fn required_unless(mut self, id: &str) -> Self {
+        self.required_unless.push(id.into());
+        self
+    }
+
+    /// Sets the default value used when the argument is not passed and the given flag, option, option argument or argument has the given value. The first matching rule wins and takes precedence over any plain default value
+    pub fn default_if(mut self, id: &str, val: &str, default: &str) -> Self {
+        self.default_if
+            .push((id.into(), val.into(), default.into()));
+        self
+    }
+
     pub fn display_as(mut self, val: &str) -> Self {
         self.raw = val.into();
         self
@@ -99,6 +189,32 @@ impl Argument {
     pub fn test_value(&self, val: &str) -> bool {
         self.valid_values.contains(&val.into())
     }
+
+    /// Checks a value passed to the argument against its valid values and validation function, if any. Returns the value in its canonical spelling, which differs from the value passed when it matched one of the valid values under the given policy
+    pub(crate) fn validate(&self, val: &str, policy: MatchPolicy) -> CmderResult<String> {
+        let invalid = |reason: String| {
+            CmderError::invalid_argument_value(&self.get_raw_value(), val, &reason)
+        };
+
+        let mut canonical = val.to_string();
+
+        if !self.valid_values.is_empty() {
+            match self.valid_values.iter().find(|v| policy.matches(v, val)) {
+                Some(v) => canonical = v.clone(),
+                None => {
+                    let reason = format!("expected one of: {}", self.valid_values.join(", "));
+                    let candidates = self.valid_values.iter().map(|v| v.as_str());
+                    return Err(invalid(reason).did_you_mean(suggest(val, candidates, policy)));
+                }
+            }
+        }
+
+        if let Some(validation_fn) = self.validation_fn {
+            validation_fn(canonical.clone()).map_err(invalid)?;
+        }
+
+        Ok(canonical)
+    }
 }
 
 // Getters for argument values
@@ -116,13 +232,54 @@ impl Argument {
     }
 
     pub fn has_default_value(&self) -> bool {
-        self.default_value.is_none()
+        self.default_value.is_some()
     }
 
     pub fn get_valid_values(&self) -> &Vec<String> {
         &self.valid_values
     }
 
+    pub fn get_env(&self) -> Option<&str> {
+        self.env.as_deref()
+    }
+
+    pub fn get_conflicts_with(&self) -> &Vec<String> {
+        &self.conflicts_with
+    }
+
+    pub fn get_requires(&self) -> &Vec<String> {
+        &self.requires
+    }
+
+    pub fn get_groups(&self) -> &Vec<String> {
+        &self.groups
+    }
+
+    pub fn get_required_if(&self) -> &Vec<(String, String)> {
+        &self.required_if
+    }
+
+    pub fn get_required_unless(&self) -> &Vec<String> {
+        &self.required_unless
+    }
+
+    pub fn get_default_if(&self) -> &Vec<(String, String, String)> {
+        &self.default_if
+    }
+
+    /// Returns the minimum and maximum number of values taken by the argument, a maximum of `None` meaning that the argument takes an unbounded number of values
+    pub fn get_num_values(&self) -> (usize, Option<usize>) {
+        if self.is_variadic {
+            (self.min_values.unwrap_or(1), self.max_values)
+        } else {
+            (1, Some(1))
+        }
+    }
+
+    pub fn allows_hyphen_values(&self) -> bool {
+        self.allow_hyphen_values
+    }
+
     pub fn is_required(&self) -> bool {
         self.is_required
     }
@@ -158,7 +315,17 @@ impl Argument {
 
 impl FormatGenerator for Argument {
     fn generate(&self, _ptrn: crate::ui::formatter::Pattern) -> (String, String) {
-        (self.raw.clone(), self.description.clone().unwrap())
+        let mut description = self.description.clone().unwrap_or_default();
+        if let Some(env) = &self.env {
+            description.push_str(&format!(" [env: {env}]"));
+        }
+        description.push_str(&describe_relations(
+            &self.conflicts_with,
+            &self.requires,
+            &self.groups,
+        ));
+
+        (self.get_raw_value(), description)
     }
 }
 
@@ -195,5 +362,39 @@ mod tests {
         // Invalid arg default value should be ignored
         arg = arg.default("6");
         assert_eq!(arg.get_default_value(), Some("3"));
+        assert!(arg.has_default_value());
+    }
+
+    #[test]
+    fn test_arg_validation() {
+        let arg = Argument::new("<level>").valid_values(vec!["low", "high"]);
+
+        assert!(arg.validate("low", MatchPolicy::default()).is_ok());
+        let err = arg.validate("medium", MatchPolicy::default()).unwrap_err();
+        assert_eq!(err.get_kind(), crate::Event::InvalidArgumentValue);
+        assert_eq!(err.get_token(), Some("medium"));
+        assert_eq!(
+            err.get_args(),
+            vec!["<level>", "medium", "expected one of: low, high"]
+        );
+        // Nothing is close enough to `medium` to be suggested
+        assert!(err.get_suggestions().is_empty());
+
+        let arg = Argument::new("<port>").validate_with(|v| match v.parse::<u16>() {
+            Ok(_) => Ok(()),
+            Err(_) => Err("not a valid port number".into()),
+        });
+
+        assert!(arg.validate("8080", MatchPolicy::default()).is_ok());
+        assert!(arg.validate("80800", MatchPolicy::default()).is_err());
+
+        let arg = Argument::new("<level>").valid_values(vec!["low", "high"]);
+        let err = arg.validate("hgih", MatchPolicy::default()).unwrap_err();
+        assert_eq!(err.get_suggestions(), &vec!["high"]);
+        assert_eq!(err.get_help(), Some("did you mean `high`?"));
+        assert_eq!(
+            err.get_message(),
+            "Invalid value `hgih` for argument `<level>`: expected one of: low, high, did you mean `high`?"
+        );
     }
 }
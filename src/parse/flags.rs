@@ -1,5 +1,7 @@
 use crate::ui::formatter::FormatGenerator;
 
+use super::{groups::describe_relations, matching::MatchPolicy};
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CmderFlag {
     pub(crate) name: String,
@@ -7,6 +9,12 @@ pub struct CmderFlag {
     pub(crate) short: String,
     pub(crate) description: String,
     pub(crate) is_global: bool,
+    pub(crate) is_negatable: bool,
+    pub(crate) max_occurrences: Option<usize>,
+    pub(crate) env: Option<String>,
+    pub(crate) conflicts_with: Vec<String>,
+    pub(crate) requires: Vec<String>,
+    pub(crate) groups: Vec<String>,
 }
 
 impl<'a> CmderFlag {
@@ -19,6 +27,12 @@ impl<'a> CmderFlag {
             long,
             description: "".into(),
             is_global: false,
+            is_negatable: false,
+            max_occurrences: None,
+            env: None,
+            conflicts_with: vec![],
+            requires: vec![],
+            groups: vec![],
         }
     }
 
@@ -34,38 +48,118 @@ impl<'a> CmderFlag {
         self
     }
 
+    /// Marks the flag as global, making it available to every subcommand of the command it is defined on, at any depth
     pub fn global(mut self, val: bool) -> Self {
         self.is_global = val;
         self
     }
+
+    /// Marks the flag as negatable, meaning that a flag such as `--color` can also be switched off by passing `--no-color`. When both forms are passed, the last one wins
+    pub fn negatable(mut self, val: bool) -> Self {
+        self.is_negatable = val;
+        self
+    }
+
+    /// Sets the maximum number of times a flag can be repeated, i.e `-vvv`. Passing the flag more times results in an error
+    pub fn max_occurrences(mut self, val: usize) -> Self {
+        self.max_occurrences = Some(val);
+        self
+    }
+
+    /// Sets an environment variable to fall back to when the flag is not passed. The value of the variable is parsed as a boolean, i.e `1/0`, `true/false` or `yes/no`
+    pub fn env(mut self, val: &'a str) -> Self {
+        self.env = Some(val.into());
+        self
+    }
+
+    /// Marks the flag as conflicting with another flag, option or argument, the two cannot be passed together
+    pub fn conflicts_with(mut self, val: &'a str) -> Self {
+        self.conflicts_with.push(val.into());
+        self
+    }
+
+    /// Marks another flag, option or argument as required whenever the flag is passed
+    pub fn requires(mut self, val: &'a str) -> Self {
+        self.requires.push(val.into());
+        self
+    }
+
+    /// Adds the flag to the argument group with the given name
+    pub fn group(mut self, val: &'a str) -> Self {
+        self.groups.push(val.into());
+        self
+    }
 }
 
-impl<'d> Default for CmderFlag {
+impl Default for CmderFlag {
     fn default() -> Self {
         Self::new("")
     }
 }
 
-pub(crate) fn resolve_flag<'f>(list: &'f [CmderFlag], val: String) -> Option<CmderFlag> {
+pub(crate) fn resolve_flag(
+    list: &[CmderFlag],
+    val: String,
+    policy: MatchPolicy,
+) -> Option<CmderFlag> {
     let mut flag = None;
 
     let val = val.as_str();
     for f in list {
-        if f.short == val || f.long == val {
+        if f.short == val || (!f.long.is_empty() && policy.matches(&f.long, val)) {
             flag = Some(f.clone());
         }
     }
     flag
 }
 
-impl<'f> FormatGenerator for CmderFlag {
+// Resolves the negated form of a negatable flag, i.e `--no-color` for the `--color` flag
+pub(crate) fn resolve_negated_flag(
+    list: &[CmderFlag],
+    val: &str,
+    policy: MatchPolicy,
+) -> Option<CmderFlag> {
+    let normalized = policy.normalize(val);
+    let name = normalized.strip_prefix("--no-")?;
+
+    list.iter()
+        .find(|f| f.is_negatable && policy.matches(&f.name, name))
+        .cloned()
+}
+
+// Parses the truthy and falsy values accepted by flags that fall back to environment variables
+pub(crate) fn parse_bool_value(val: &str) -> Option<bool> {
+    match val.to_lowercase().as_str() {
+        "1" | "true" | "yes" => Some(true),
+        "0" | "false" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+impl FormatGenerator for CmderFlag {
     fn generate(&self, _ptrn: crate::ui::formatter::Pattern) -> (String, String) {
         let short: String = if !self.short.is_empty() {
             format!("{},", self.short)
         } else {
             "  ".into()
         };
-        (format!("{} {}", short, self.long), self.description.clone())
+        let long = if self.is_negatable {
+            format!("--[no-]{}", self.name)
+        } else {
+            self.long.clone()
+        };
+
+        let mut description = self.description.clone();
+        if let Some(env) = &self.env {
+            description.push_str(&format!(" [env: {env}]"));
+        }
+        description.push_str(&describe_relations(
+            &self.conflicts_with,
+            &self.requires,
+            &self.groups,
+        ));
+
+        (format!("{} {}", short, long), description)
     }
 }
 
@@ -89,6 +183,12 @@ pub(crate) fn new_flag(val: &str, help: &'static str) -> CmderFlag {
         short: short.into(),
         description: help.into(),
         is_global: false,
+        is_negatable: false,
+        max_occurrences: None,
+        env: None,
+        conflicts_with: vec![],
+        requires: vec![],
+        groups: vec![],
     }
 }
 
@@ -110,4 +210,42 @@ mod tests {
         assert_eq!(f.short, "-h".to_owned());
         assert_eq!(f.description, "Help flag")
     }
+
+    #[test]
+    fn test_negatable_flag() {
+        let f = CmderFlag::new("color")
+            .help("Colorize output")
+            .negatable(true);
+        let list = vec![f.clone(), CmderFlag::new("quiet")];
+
+        assert!(f.is_negatable);
+        assert_eq!(
+            resolve_negated_flag(&list, "--no-color", MatchPolicy::default()),
+            Some(f.clone())
+        );
+        assert_eq!(
+            resolve_negated_flag(&list, "--no-quiet", MatchPolicy::default()),
+            None
+        );
+        assert_eq!(
+            resolve_negated_flag(&list, "--color", MatchPolicy::default()),
+            None
+        );
+
+        let (leading, _) = f.generate(crate::Pattern::Legacy);
+        assert_eq!(leading, "   --[no-]color");
+    }
+
+    #[test]
+    fn test_flag_env() {
+        let f = CmderFlag::new("verbose")
+            .help("Verbose output")
+            .env("APP_VERBOSE");
+        let (_, floating) = f.generate(crate::Pattern::Legacy);
+
+        assert_eq!(floating, "Verbose output [env: APP_VERBOSE]");
+        assert_eq!(parse_bool_value("YES"), Some(true));
+        assert_eq!(parse_bool_value("0"), Some(false));
+        assert_eq!(parse_bool_value("maybe"), None);
+    }
 }
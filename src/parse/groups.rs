@@ -0,0 +1,116 @@
+/// Controls how many members of an argument group can be passed at once
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupPolicy {
+    /// Exactly one member of the group must be passed
+    ExactlyOne,
+    /// At least one member of the group must be passed
+    AtLeastOne,
+    /// No more than one member of the group can be passed, the group can also be omitted entirely
+    AtMostOne,
+}
+
+/// A named set of flags, options and arguments that are validated together. Members can either be listed on the group itself or join it through the `group` method of the flag, option or argument
+///
+/// ```
+/// use cmder::{ArgGroup, CmderOption, GroupPolicy, Program};
+///
+/// let mut program = Program::new();
+///
+/// program
+///     .add_option(CmderOption::new("file").argument("<path>").group("source"))
+///     .add_option(CmderOption::new("url").argument("<link>").group("source"))
+///     .add_group(ArgGroup::new("source").policy(GroupPolicy::ExactlyOne));
+///
+/// assert!(program.try_parse_from(vec!["fetch"]).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArgGroup {
+    pub(crate) name: String,
+    pub(crate) members: Vec<String>,
+    pub(crate) policy: GroupPolicy,
+}
+
+impl ArgGroup {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.into(),
+            members: vec![],
+            policy: GroupPolicy::AtMostOne,
+        }
+    }
+
+    pub fn policy(mut self, val: GroupPolicy) -> Self {
+        self.policy = val;
+        self
+    }
+
+    /// Adds a member to the group, flags and options are referred to by their long or short name while arguments are referred to by their name, i.e `<file>`
+    pub fn member(mut self, val: &str) -> Self {
+        self.members.push(val.into());
+        self
+    }
+
+    pub fn members(mut self, vals: Vec<&str>) -> Self {
+        for v in vals {
+            self.members.push(v.into());
+        }
+        self
+    }
+}
+
+// Getters for group values
+impl ArgGroup {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_members(&self) -> &Vec<String> {
+        &self.members
+    }
+
+    pub fn get_policy(&self) -> GroupPolicy {
+        self.policy
+    }
+}
+
+// Describes the relations of a flag, option or argument to other items, shown in help next to the item
+pub(crate) fn describe_relations(
+    conflicts_with: &[String],
+    requires: &[String],
+    groups: &[String],
+) -> String {
+    let mut res = String::new();
+
+    for (label, vals) in [
+        ("conflicts with", conflicts_with),
+        ("requires", requires),
+        ("group", groups),
+    ] {
+        if !vals.is_empty() {
+            res.push_str(&format!(" [{label}: {}]", vals.join(", ")));
+        }
+    }
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_creation() {
+        let group = ArgGroup::new("output")
+            .member("--json")
+            .members(vec!["--table", "<format>"])
+            .policy(GroupPolicy::ExactlyOne);
+
+        assert_eq!(group.get_name(), "output");
+        assert_eq!(group.get_members(), &vec!["--json", "--table", "<format>"]);
+        assert_eq!(group.get_policy(), GroupPolicy::ExactlyOne);
+        assert_eq!(ArgGroup::new("x").get_policy(), GroupPolicy::AtMostOne);
+
+        let relations = describe_relations(&["--table".into()], &[], &["output".into()]);
+        assert_eq!(relations, " [conflicts with: --table] [group: output]");
+    }
+}
@@ -1,6 +1,8 @@
 #![allow(dead_code)]
 
-use crate::core::Command;
+use std::{collections::BTreeMap, fmt::Display, str::FromStr};
+
+use crate::core::{CmderError, CmderResult, Command};
 
 use super::{CmderFlag, CmderOption};
 
@@ -13,6 +15,10 @@ pub struct ParserMatches<'pm> {
     pub(crate) option_matches: Vec<OptionsMatches>,
     pub(crate) arg_matches: Vec<ArgsMatches>,
     pub(crate) positional_args: Vec<String>,
+    // The deprecated aliases used to invoke commands, along with the name of the command and the warning to emit
+    pub(crate) deprecated_aliases: Vec<(String, String, String)>,
+    // Whether conversion errors are kept from the event system, which is the case for matches returned by `try_parse` and `try_parse_from`
+    pub(crate) is_silent: bool,
 }
 
 #[derive(PartialEq, Eq, Debug, Clone)]
@@ -20,6 +26,7 @@ pub(crate) struct FlagsMatches {
     pub(crate) cursor_index: usize,
     pub(crate) flag: CmderFlag,
     pub(crate) appearance_count: usize,
+    pub(crate) is_negated: bool,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Eq)]
@@ -28,15 +35,37 @@ pub(crate) struct OptionsMatches {
     pub(crate) option: CmderOption,
     pub(crate) args: Vec<ArgsMatches>,
     pub(crate) appearance_count: usize,
+    pub(crate) value_forms: Vec<ValueForm>,
+    // The values passed in each occurrence of the option, kept together in command-line order
+    pub(crate) occurrences: Vec<Vec<String>>,
+}
+
+/// Describes how the value of an option was passed in a given occurrence of the option
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueForm {
+    /// The value was passed as a separate arg, i.e `--port 8080` or `-p 8080`
+    Separate,
+    /// The value was attached to the option with an `=`, i.e `--port=8080` or `-p=8080`
+    Equals,
+    /// The value was glued onto a short option, i.e `-p8080`
+    Glued,
+    /// The option was not passed and its value was read from an environment variable instead
+    Env,
+    /// The option was not passed and the default values of its arguments are used instead
+    Default,
+    /// The option was passed without a value, i.e `--color` for a `--color[=when]` option. Its arguments fall back to their default values, if any
+    Omitted,
 }
 
-impl<'o> OptionsMatches {
+impl OptionsMatches {
     pub(crate) fn new() -> Self {
         Self {
             appearance_count: 0,
             args: vec![],
             cursor_index: 0,
             option: CmderOption::default(),
+            value_forms: vec![],
+            occurrences: vec![],
         }
     }
 
@@ -58,14 +87,54 @@ pub(crate) struct ArgsMatches {
     pub(crate) cursor_index: usize,
     pub(crate) raw_value: String,
     pub(crate) instance_of: String,
+    // The individual values matched, variadic arguments can match more than one value
+    pub(crate) values: Vec<String>,
+    // Whether the value is the default value of the argument rather than one that was passed
+    pub(crate) is_default: bool,
+    // The index of the token each value was taken from, not counting the binary name. Values read from the environment or from defaults have no index
+    pub(crate) indices: Vec<usize>,
 }
 
-impl<'args> ArgsMatches {
+impl ArgsMatches {
     pub(crate) fn new() -> Self {
         Self {
             cursor_index: 0,
             raw_value: String::new(),
             instance_of: String::new(),
+            values: vec![],
+            is_default: false,
+            indices: vec![],
+        }
+    }
+
+    pub(crate) fn with_values(
+        cursor_index: usize,
+        instance_of: String,
+        values: Vec<String>,
+    ) -> Self {
+        Self {
+            cursor_index,
+            raw_value: values.join(" "),
+            instance_of,
+            values,
+            is_default: false,
+            indices: vec![],
+        }
+    }
+
+    pub(crate) fn from_tokens(instance_of: String, tokens: &[(usize, String)]) -> Self {
+        let values = tokens.iter().map(|(_, v)| v.clone()).collect();
+
+        Self {
+            indices: tokens.iter().map(|(i, _)| *i).collect(),
+            ..Self::with_values(tokens[0].0, instance_of, values)
+        }
+    }
+
+    pub(crate) fn from_default(instance_of: String, val: &str) -> Self {
+        Self {
+            is_default: true,
+            ..Self::with_values(0, instance_of, vec![val.into()])
         }
     }
 }
@@ -80,6 +149,8 @@ impl<'a> ParserMatches<'a> {
             arg_matches: vec![],
             option_matches: vec![],
             positional_args: vec![],
+            deprecated_aliases: vec![],
+            is_silent: false,
         }
     }
 
@@ -116,6 +187,14 @@ impl<'a> ParserMatches<'a> {
         self.positional_args.clone()
     }
 
+    /// Returns the warnings of any deprecated command aliases that were used, in command-line order
+    pub fn get_deprecation_warnings(&self) -> Vec<&str> {
+        self.deprecated_aliases
+            .iter()
+            .map(|(_, _, warning)| warning.as_str())
+            .collect()
+    }
+
     pub fn get_option_arg(&self, val: &str) -> Option<String> {
         let mut arg = None;
         self.option_matches.iter().for_each(|o| {
@@ -141,6 +220,167 @@ impl<'a> ParserMatches<'a> {
         instances
     }
 
+    /// Returns the value of an option whose value is optional. `None` means that the option was not passed, `Some(None)` that it was passed without a value and no default value exists, while `Some(Some(value))` contains the value passed or the default value used when the option was passed on its own. Only the last occurrence of the option is considered
+    ///
+    /// ```
+    /// use cmder::Program;
+    ///
+    /// let mut program = Program::new();
+    /// program.option("--color[=when]", "When to colorize output");
+    ///
+    /// let matches = program.try_parse_from(vec!["ls", "--color"]).unwrap();
+    /// assert_eq!(matches.get_option_value("--color"), Some(None));
+    /// ```
+    pub fn get_option_value(&self, val: &str) -> Option<Option<String>> {
+        self.option_matches
+            .iter()
+            .find(|o| o.appearance_count > 0 && o.contains_option(val))
+            .and_then(|o| o.occurrences.last())
+            .map(|values| (!values.is_empty()).then(|| values.join(" ")))
+    }
+
+    /// Returns the values of each occurrence of an option, grouped per occurrence in the order the occurrences were passed. Returns an empty vector if the option was not passed
+    ///
+    /// ```
+    /// use cmder::Program;
+    ///
+    /// let mut program = Program::new();
+    /// program.option("-m --mount <src> <dst>", "Mount a volume");
+    ///
+    /// let matches = program
+    ///     .try_parse_from(vec!["run", "-m", "/a", "/x", "--mount", "/b", "/y"])
+    ///     .unwrap();
+    ///
+    /// assert_eq!(
+    ///     matches.get_occurrences("--mount"),
+    ///     vec![vec!["/a", "/x"], vec!["/b", "/y"]]
+    /// );
+    /// ```
+    pub fn get_occurrences(&self, val: &str) -> Vec<Vec<String>> {
+        self.option_matches
+            .iter()
+            .find(|o| o.contains_option(val))
+            .map(|o| o.occurrences.clone())
+            .unwrap_or_default()
+    }
+
+    /// Returns the `key=value` pairs passed to a key-value option across all its occurrences. When a key is passed more than once, the last value wins
+    ///
+    /// ```
+    /// use cmder::{CmderOption, Program};
+    ///
+    /// let mut program = Program::new();
+    /// program.add_option(
+    ///     CmderOption::new("define")
+    ///         .short('D')
+    ///         .argument("<pair>")
+    ///         .key_value(true),
+    /// );
+    ///
+    /// let matches = program
+    ///     .try_parse_from(vec!["build", "-D", "mode=release", "-Dlto=true"])
+    ///     .unwrap();
+    /// let map = matches.get_map("-D");
+    ///
+    /// assert_eq!(map["mode"], "release");
+    /// assert_eq!(map["lto"], "true");
+    /// ```
+    pub fn get_map(&self, val: &str) -> BTreeMap<String, String> {
+        self.option_matches
+            .iter()
+            .filter(|o| o.contains_option(val))
+            .flat_map(|o| o.args.iter().flat_map(|a| a.values.iter()))
+            .filter_map(|v| v.split_once('='))
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    /// Returns the positions of the tokens that were assigned to the given argument, not counting the binary name. Values read from the environment or from default values have no position
+    pub fn get_arg_indices(&self, val: &str) -> Vec<usize> {
+        self.arg_matches
+            .iter()
+            .filter(|a| a.instance_of == val)
+            .flat_map(|a| a.indices.clone())
+            .collect()
+    }
+
+    /// Returns the form in which values were passed to the given option, one entry per occurrence of the option
+    pub fn get_value_forms(&self, val: &str) -> Vec<ValueForm> {
+        self.option_matches
+            .iter()
+            .find(|o| o.contains_option(val))
+            .map(|o| o.value_forms.clone())
+            .unwrap_or_default()
+    }
+
+    /// Returns the value of the given argument or option argument converted into the desired type. If the value cannot be converted, the error is emitted through the `InvalidArgumentValue` event, whose default listener prints the error and exits the process. Matches returned by `try_parse` and `try_parse_from` never emit events, so `None` is returned instead. Use `try_get_value` to handle the error yourself
+    ///
+    /// ```
+    /// use cmder::Program;
+    ///
+    /// let mut program = Program::new();
+    ///
+    /// program
+    ///     .option("-p --port <port-number>", "The port to use")
+    ///     .action(|m| {
+    ///         let port: Option<u16> = m.get_value("<port-number>");
+    ///         assert_eq!(port, Some(8080));
+    ///     });
+    ///
+    /// program.parse_from(vec!["server", "-p", "8080"]);
+    /// ```
+    pub fn get_value<T>(&self, val: &str) -> Option<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.try_get_value(val).unwrap_or_else(|e| {
+            self.report_error(e);
+            None
+        })
+    }
+
+    /// Similar to `get_value` but returns all the values of a variadic argument or all the instances of an option argument converted into the desired type. Conversion errors are handled the same way as in `get_value`
+    pub fn get_values<T>(&self, val: &str) -> Vec<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.try_get_values(val).unwrap_or_else(|e| {
+            self.report_error(e);
+            vec![]
+        })
+    }
+
+    /// Returns the value of the given argument or option argument converted into the desired type, or an `InvalidArgumentValue` error containing the argument name and the value passed if the conversion fails. Returns `Ok(None)` if no value was passed
+    pub fn try_get_value<T>(&self, val: &str) -> CmderResult<Option<T>>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        match self.get_arg(val).or_else(|| self.get_option_arg(val)) {
+            Some(v) => convert_value(val, &v).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// The fallible version of `get_values`
+    pub fn try_get_values<T>(&self, val: &str) -> CmderResult<Vec<T>>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let arg_values = self.arg_matches.iter();
+        let opt_values = self.option_matches.iter().flat_map(|o| o.args.iter());
+
+        arg_values
+            .chain(opt_values)
+            .filter(|a| a.instance_of == val)
+            .flat_map(|a| a.values.iter())
+            .map(|v| convert_value(val, v))
+            .collect()
+    }
+
     pub fn get_flag(&self, val: &str) -> Option<CmderFlag> {
         self.flag_matches
             .iter()
@@ -156,25 +396,33 @@ impl<'a> ParserMatches<'a> {
             .iter()
             .find(|opc| {
                 let option = &opc.option;
-                option.long == val || option.short == val
+                opc.appearance_count > 0 && (option.long == val || option.short == val)
             })
             .map(|opm| opm.option.clone())
     }
 
+    /// Returns true if the given flag was passed and is switched on. A negatable flag whose last occurrence was in its `--no-` form is considered switched off
     pub fn contains_flag(&self, val: &str) -> bool {
-        self.flag_matches.iter().any(|f| {
-            let flag = &f.flag;
-            flag.short == val || flag.long == val
-        })
+        self.get_flag_state(val) == Some(true)
+    }
+
+    /// Returns the explicit on/off state of a flag: `Some(true)` if it was switched on, `Some(false)` if it was negated(`--no-color`) and `None` if it was not passed at all
+    pub fn get_flag_state(&self, val: &str) -> Option<bool> {
+        self.flag_matches
+            .iter()
+            .find(|f| {
+                let flag = &f.flag;
+                flag.short == val || flag.long == val
+            })
+            .map(|f| !f.is_negated)
     }
 
+    /// Returns true if the given option was passed. Options that were not passed but whose arguments have default values are not considered present
     pub fn contains_option(&self, val: &str) -> bool {
-        self.option_matches.iter().any(|o| {
-            let op = &o.option;
-            op.short == val || op.long == val
-        })
+        self.get_option_count(val) > 0
     }
 
+    /// Returns the number of times a flag was passed, i.e 3 for `-vvv` or `-v -v -v`
     pub fn get_flag_count(&self, val: &str) -> i32 {
         let mut count = 0;
 
@@ -182,13 +430,14 @@ impl<'a> ParserMatches<'a> {
             let flag = &fc.flag;
 
             if flag.short == val || flag.long == val {
-                count += 1;
+                count += fc.appearance_count as i32;
             }
         }
 
         count
     }
 
+    /// Returns the number of times an option was passed
     pub fn get_option_count(&self, val: &str) -> i32 {
         let mut count = 0;
 
@@ -196,10 +445,33 @@ impl<'a> ParserMatches<'a> {
             let flag = &fc.option;
 
             if flag.short == val || flag.long == val {
-                count += 1;
+                count += fc.appearance_count as i32;
             }
         }
 
         count
     }
+
+    fn report_error(&self, err: CmderError) {
+        if self.is_silent {
+            return;
+        }
+
+        let mut cfg = err.into_event_cfg(self.root_cmd);
+
+        if let Some(cmd) = self.matched_cmd {
+            cfg = cfg.set_matched_cmd(cmd);
+        }
+
+        self.root_cmd.emit(cfg);
+    }
+}
+
+fn convert_value<T>(name: &str, val: &str) -> CmderResult<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    val.parse::<T>()
+        .map_err(|e| CmderError::invalid_argument_value(name, val, &e.to_string()))
 }
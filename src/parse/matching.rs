@@ -0,0 +1,58 @@
+use crate::core::{Command, Setting};
+
+// Controls how long flag and option names, subcommand names and valid values are matched against the args passed. Short flags are always matched exactly
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct MatchPolicy {
+    pub(crate) ignore_case: bool,
+    pub(crate) ignore_separators: bool,
+}
+
+impl MatchPolicy {
+    // Like every matching and parsing setting, `IgnoreCase` and `IgnoreSeparators` apply to the command they are set on and to all of its subcommands
+    pub(crate) fn from_cmd(cmd: &Command) -> Self {
+        Self {
+            ignore_case: cmd.get_inherited_setting(Setting::IgnoreCase),
+            ignore_separators: cmd.get_inherited_setting(Setting::IgnoreSeparators),
+        }
+    }
+
+    // Folds a value into the form used for comparisons, i.e `--Dry_Run` into `--dry-run`
+    pub(crate) fn normalize(&self, val: &str) -> String {
+        let mut res = val.to_string();
+
+        if self.ignore_case {
+            res = res.to_lowercase();
+        }
+
+        if self.ignore_separators {
+            res = res.replace('_', "-");
+        }
+
+        res
+    }
+
+    pub(crate) fn matches(&self, canonical: &str, val: &str) -> bool {
+        canonical == val || self.normalize(canonical) == self.normalize(val)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_policy() {
+        let exact = MatchPolicy::default();
+        assert!(exact.matches("--dry-run", "--dry-run"));
+        assert!(!exact.matches("--dry-run", "--Dry-Run"));
+
+        let policy = MatchPolicy {
+            ignore_case: true,
+            ignore_separators: true,
+        };
+        assert!(policy.matches("--dry-run", "--Dry_Run"));
+        assert!(policy.matches("container", "CONTAINER"));
+        assert!(!policy.matches("--dry-run", "--dryrun"));
+        assert_eq!(policy.normalize("--Dry_Run"), "--dry-run");
+    }
+}
@@ -1,13 +1,16 @@
 pub mod args;
 pub mod flags;
+pub mod groups;
 pub mod matches;
+pub(crate) mod matching;
 pub mod options;
 pub mod parser;
 
 pub use args::Argument;
-pub(crate) use flags::resolve_flag;
 pub use flags::CmderFlag;
-pub use matches::ParserMatches;
+pub(crate) use flags::{parse_bool_value, resolve_flag, resolve_negated_flag};
+pub use groups::{ArgGroup, GroupPolicy};
+pub use matches::{ParserMatches, ValueForm};
 pub(crate) use options::resolve_option;
 pub use options::CmderOption;
 pub use parser::Parser;
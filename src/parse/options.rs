@@ -1,6 +1,6 @@
 use crate::ui::formatter::FormatGenerator;
 
-use super::Argument;
+use super::{groups::describe_relations, matching::MatchPolicy, Argument};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CmderOption {
@@ -11,6 +11,16 @@ pub struct CmderOption {
     pub(crate) description: String,
     pub(crate) is_required: bool,
     pub(crate) is_global: bool,
+    pub(crate) env: Option<String>,
+    pub(crate) conflicts_with: Vec<String>,
+    pub(crate) requires: Vec<String>,
+    pub(crate) groups: Vec<String>,
+    pub(crate) required_if: Vec<(String, String)>,
+    pub(crate) required_unless: Vec<String>,
+    pub(crate) default_if: Vec<(String, String, String)>,
+    pub(crate) value_delimiter: Option<char>,
+    pub(crate) is_key_value: bool,
+    pub(crate) allow_duplicate_keys: bool,
 }
 
 impl<'b> CmderOption {
@@ -25,6 +35,16 @@ impl<'b> CmderOption {
             description: "".into(),
             is_required: false,
             is_global: false,
+            env: None,
+            conflicts_with: vec![],
+            requires: vec![],
+            groups: vec![],
+            required_if: vec![],
+            required_unless: vec![],
+            default_if: vec![],
+            value_delimiter: None,
+            is_key_value: false,
+            allow_duplicate_keys: true,
         }
     }
 
@@ -45,11 +65,95 @@ impl<'b> CmderOption {
         self
     }
 
+    /// Marks the option as global, making it available to every subcommand of the command it is defined on, at any depth
     pub fn global(mut self, v: bool) -> Self {
         self.is_global = v;
         self
     }
 
+    /// Sets an environment variable whose value is used when the option is not passed. The environment variable takes precedence over any default values of the option arguments
+    pub fn env(mut self, val: &'b str) -> Self {
+        self.env = Some(val.into());
+        self
+    }
+
+    /// Marks the option as conflicting with another flag, option or argument, the two cannot be passed together
+    pub fn conflicts_with(mut self, val: &'b str) -> Self {
+        self.conflicts_with.push(val.into());
+        self
+    }
+
+    /// Marks another flag, option or argument as required whenever the option is passed
+    pub fn requires(mut self, val: &'b str) -> Self {
+        self.requires.push(val.into());
+        self
+    }
+
+    /// Adds the option to the argument group with the given name
+    pub fn group(mut self, val: &'b str) -> Self {
+        self.groups.push(val.into());
+        self
+    }
+
+    /// Marks the option as required whenever the given flag, option, option argument or argument has the given value. Flags have the value `true` when switched on and `false` when negated
+    pub fn required_if(mut self, id: &'b str, val: &'b str) -> Self {
+        self.required_if.push((id.into(), val.into()));
+        self
+    }
+
+    /// Marks the option as required unless the given flag, option, option argument or argument is passed
+    pub fn required_unless(mut self, id: &'b str) -> Self {
+        self.required_unless.push(id.into());
+        self
+    }
+
+    /// Sets the default value used when the option is not passed and the given flag, option, option argument or argument has the given value. The first matching rule wins and takes precedence over any plain default value
+    pub fn default_if(mut self, id: &'b str, val: &'b str, default: &'b str) -> Self {
+        self.default_if
+            .push((id.into(), val.into(), default.into()));
+        self
+    }
+
+    /// Sets a delimiter used to split the value of a single occurrence of the option into multiple values, i.e `--tags a,b,c`. The delimiter can be escaped with a backslash(`a\,b`). Only options taking a single argument are split, the delimiter is ignored by the others
+    pub fn value_delimiter(mut self, val: char) -> Self {
+        self.value_delimiter = Some(val);
+        self
+    }
+
+    /// Marks the values of the option as `key=value` pairs, i.e `-D name=value`. The pairs passed across all occurrences can be retrieved through `ParserMatches::get_map`
+    pub fn key_value(mut self, val: bool) -> Self {
+        self.is_key_value = val;
+        self
+    }
+
+    /// Controls whether a key can be passed more than once to a key-value option. When allowed, which is the default, the last value passed wins
+    pub fn allow_duplicate_keys(mut self, val: bool) -> Self {
+        self.allow_duplicate_keys = val;
+        self
+    }
+
+    /// Sets the exact number of values taken by the last argument of the option
+    pub fn num_values(self, n: usize) -> Self {
+        self.map_last_argument(|a| a.num_values(n))
+    }
+
+    /// Sets the minimum number of values taken by the last argument of the option
+    pub fn min_values(self, n: usize) -> Self {
+        self.map_last_argument(|a| a.min_values(n))
+    }
+
+    /// Sets the maximum number of values taken by the last argument of the option per occurrence of the option
+    pub fn max_values(self, n: usize) -> Self {
+        self.map_last_argument(|a| a.max_values(n))
+    }
+
+    fn map_last_argument(mut self, f: impl FnOnce(Argument) -> Argument) -> Self {
+        if let Some(arg) = self.arguments.pop() {
+            self.arguments.push(f(arg));
+        }
+        self
+    }
+
     pub fn argument(mut self, val: &'b str) -> Self {
         self.arguments.push(Argument::new(val));
         self
@@ -61,25 +165,29 @@ impl<'b> CmderOption {
     }
 }
 
-impl<'d> Default for CmderOption {
+impl Default for CmderOption {
     fn default() -> Self {
         Self::new("")
     }
 }
 
-pub(crate) fn resolve_option<'o>(list: &'o [CmderOption], val: String) -> Option<CmderOption> {
+pub(crate) fn resolve_option(
+    list: &[CmderOption],
+    val: String,
+    policy: MatchPolicy,
+) -> Option<CmderOption> {
     let mut flag = None;
 
     let val = val.as_str();
     for f in list {
-        if f.short == val || f.long == val {
+        if f.short == val || (!f.long.is_empty() && policy.matches(&f.long, val)) {
             flag = Some(f.clone());
         }
     }
     flag
 }
 
-impl<'f> FormatGenerator for CmderOption {
+impl FormatGenerator for CmderOption {
     fn generate(&self, _ptrn: crate::ui::formatter::Pattern) -> (String, String) {
         let short: String = if !self.short.is_empty() {
             format!("{},", self.short)
@@ -87,11 +195,35 @@ impl<'f> FormatGenerator for CmderOption {
             "  ".into()
         };
 
+        let mut description = self.description.clone();
+        if let Some(env) = &self.env {
+            description.push_str(&format!(" [env: {env}]"));
+        }
+        description.push_str(&describe_relations(
+            &self.conflicts_with,
+            &self.requires,
+            &self.groups,
+        ));
+
+        // A single optional value can only be attached to the option, i.e `--color[=when]`
+        if let [arg] = &self.arguments[..] {
+            if !arg.is_required() {
+                let raw = arg.get_raw_value();
+                return (
+                    format!("{} {}[={}", short, self.long, &raw[1..]),
+                    description,
+                );
+            }
+        }
+
         let args = if !self.arguments.is_empty() {
             let mut raw = String::new();
 
             for a in &self.arguments {
                 raw.push_str(&(a.get_raw_value()));
+                if let (Some(delim), 1) = (self.value_delimiter, self.arguments.len()) {
+                    raw.push_str(&format!("{delim}..."));
+                }
                 raw.push(' ');
             }
 
@@ -100,10 +232,7 @@ impl<'f> FormatGenerator for CmderOption {
             "".into()
         };
 
-        (
-            format!("{} {} {}", short, self.long, args),
-            self.description.clone(),
-        )
+        (format!("{} {} {}", short, self.long, args), description)
     }
 }
 
@@ -117,16 +246,23 @@ pub(crate) fn new_option(val: &str, help: &'static str, required: bool) -> Cmder
 
     for v in &values {
         if v.starts_with("--") {
-            long = v;
+            // An optional value can be attached to the long name, i.e `--color[=when]`
+            match v.split_once("[=") {
+                Some((name, arg)) => {
+                    long = name;
+                    raw_args.push(format!("[{arg}"));
+                }
+                None => long = v,
+            }
         } else if v.starts_with('-') {
             short = v
         } else {
-            raw_args.push(v);
+            raw_args.push(v.to_string());
         }
     }
 
     for a in raw_args {
-        let arg = Argument::new(a);
+        let arg = Argument::new(&a);
         args.push(arg);
     }
 
@@ -138,6 +274,16 @@ pub(crate) fn new_option(val: &str, help: &'static str, required: bool) -> Cmder
         description: help.into(),
         is_required: required,
         is_global: false,
+        env: None,
+        conflicts_with: vec![],
+        requires: vec![],
+        groups: vec![],
+        required_if: vec![],
+        required_unless: vec![],
+        default_if: vec![],
+        value_delimiter: None,
+        is_key_value: false,
+        allow_duplicate_keys: true,
     }
 }
 
@@ -161,4 +307,37 @@ mod tests {
         assert_eq!(opt.long, "--port".to_owned());
         assert_eq!(opt.arguments.len(), 1);
     }
+
+    #[test]
+    fn test_optional_value_syntax() {
+        let opt = new_option("-c --color[=when]", "When to colorize output", false);
+
+        assert_eq!(opt.long, "--color");
+        assert_eq!(opt.short, "-c");
+        assert_eq!(opt.arguments.len(), 1);
+        assert!(!opt.arguments[0].is_required());
+        assert_eq!(opt.arguments[0].get_raw_value(), "[when]");
+
+        let (leading, _) = opt.generate(crate::Pattern::Legacy);
+        assert_eq!(leading, "-c, --color[=when]");
+        assert_eq!(new_option("-c --color [when]", "", false), opt.help(""));
+    }
+
+    #[test]
+    fn test_value_delimiter_help() {
+        let opt = CmderOption::new("tags")
+            .argument("<tag>")
+            .value_delimiter(',');
+        let (leading, _) = opt.generate(crate::Pattern::Legacy);
+
+        assert_eq!(opt.value_delimiter, Some(','));
+        assert_eq!(leading.trim_end(), "   --tags <tag>,...");
+
+        let opt = CmderOption::new("range")
+            .argument("<a>")
+            .argument("<b>")
+            .value_delimiter(',');
+        let (leading, _) = opt.generate(crate::Pattern::Legacy);
+        assert_eq!(leading.trim_end(), "   --range <a> <b>");
+    }
 }
@@ -4,19 +4,28 @@ use std::collections::HashMap;
 
 use crate::core::Command;
 use crate::core::EventConfig;
-use crate::core::{CmderError, CmderResult};
+use crate::core::{CmderError, CmderResult, Setting};
+use crate::utils::suggest;
 use crate::Event;
 
-use super::matches::{ArgsMatches, CommandMatches, FlagsMatches, OptionsMatches, ParserMatches};
-use super::Argument;
-use super::{resolve_flag, resolve_option, CmderFlag, CmderOption};
+use super::matches::{
+    ArgsMatches, CommandMatches, FlagsMatches, OptionsMatches, ParserMatches, ValueForm,
+};
+use super::{matching::MatchPolicy, ArgGroup, Argument, GroupPolicy};
+use super::{
+    parse_bool_value, resolve_flag, resolve_negated_flag, resolve_option, CmderFlag, CmderOption,
+};
 
 pub struct Parser<'a> {
     cmd: &'a Command<'a>,
     cursor_index: usize,
-    marked_args: Vec<(String, bool)>,
-    valid_arg_found: bool,
     allow_trailing_values: bool,
+    // Values not consumed by flags or options, along with their index in the raw args. They are mapped onto the arguments of the matched command once all args have been walked through
+    positional_values: Vec<(usize, String)>,
+    // Values passed after `--`. They fill any arguments left unfilled by the positional values, the rest are returned as positional args
+    trailing_values: Vec<(usize, String)>,
+    // The commands matched so far, starting with the root command
+    cmd_path: Vec<&'a Command<'a>>,
     parser_cfg: ParserMatches<'a>,
 }
 
@@ -25,243 +34,2091 @@ impl<'p> Parser<'p> {
         Self {
             cmd,
             cursor_index: 0,
-            marked_args: vec![],
-            valid_arg_found: false,
             allow_trailing_values: false,
+            positional_values: vec![],
+            trailing_values: vec![],
+            cmd_path: vec![cmd],
             parser_cfg: ParserMatches::new(0, cmd),
         }
     }
 
     pub fn parse(&mut self, args: Vec<String>) -> CmderResult<ParserMatches<'p>> {
-        if args.is_empty() {
-            // handle empty args
-        }
-
-        // Mark all the args as untouched yet
-        for a in &args {
-            self.marked_args.push((a.clone(), false));
-        }
+        self.parse_tokens(args)
+            .map_err(|e| e.matched_cmd(&self.cmd_names().join(" ")))
+    }
 
+    fn parse_tokens(&mut self, args: Vec<String>) -> CmderResult<ParserMatches<'p>> {
         self.parser_cfg.arg_count = args.len();
         self.parser_cfg.matched_cmd = Some(self.cmd);
 
-        for (cursor_index, arg) in args.iter().enumerate() {
+        let mut cursor_index = 0;
+
+        while cursor_index < args.len() {
             let cmd = self.cmd;
+            let arg = &args[cursor_index];
+            let rest = &args[(cursor_index + 1)..];
             self.cursor_index = cursor_index;
 
-            if arg.is_empty() {
+            if self.allow_trailing_values {
+                self.trailing_values.push((cursor_index, arg.clone()));
+            } else if arg.is_empty() {
                 // ignore empty args
-                continue;
-            } else if arg.starts_with('-') {
-                // It is either a flag, an option, or '--', or unknown option/flag
-                if let Some(flag) = resolve_flag(cmd.get_flags(), arg.clone()) {
-                    // parse flag
-                    if !self.allow_trailing_values {
-                        self.marked_args[cursor_index].1 = true;
-                        self.parse_flag(flag)
-                    }
-                } else if let Some(opt) = resolve_option(cmd.get_options(), arg.clone()) {
-                    // parse option
-                    self.marked_args[cursor_index].1 = true;
-                    self.cursor_index += 1;
-                    // Parse any args following option
-                    self.parse_option(opt, args[(cursor_index + 1)..].to_vec())?
-                } else if arg.contains('=') && !self.allow_trailing_values {
-                    // Split the arg into key and value
-                    let parts = arg.split('=').collect::<Vec<_>>();
-
-                    if let Some(opt) = resolve_option(cmd.get_options(), parts[0].into()) {
-                        // parse option using parts[1]
-                        let mut temp_args: Vec<String> = vec![parts[1].into()];
-                        temp_args.extend_from_slice(&args[(cursor_index + 1)..]);
-
-                        self.parse_option(opt, temp_args)?
-                    }
-                } else if arg == "--" {
-                    self.allow_trailing_values = true;
-                    self.marked_args[cursor_index].1 = true;
-                    // parse positional args
-                    for (i, a) in args[(cursor_index + 1)..].to_vec().iter().enumerate() {
-                        self.marked_args[(cursor_index + i) + 1].1 = true;
-                        // TODO: Refactor pos_args field into vec of arg_matches
-                        self.parser_cfg.positional_args.push(a.to_owned());
-                    }
-                } else if !self.is_marked(cursor_index) {
-                    return Err(CmderError::UnknownOption(arg.clone()));
-                }
-            } else if let Some(sub_cmd) = cmd.find_subcommand(arg) {
-                self.marked_args[cursor_index].1 = true;
-                // check if command pstn is valid
-                if self.valid_arg_found {
-                    // TODO: return invalid ctx error
-                    return Err(CmderError::UnresolvedArgument(vec![arg.clone()]));
+            } else if arg == "--" && !self.setting(Setting::PassThroughDoubleDash) {
+                self.allow_trailing_values = true;
+            } else if arg.starts_with('-') && arg != "--" && !self.is_positional_hyphen_value(arg) {
+                // It is either a flag, an option, a cluster of short flags, or an unknown option/flag
+                cursor_index += self.parse_dash_arg(arg, rest)?;
+            } else if let Some(sub_cmd) = self.find_subcommand(arg)? {
+                if self.positional_values.is_empty() {
+                    self.parse_cmd(sub_cmd);
                 } else {
-                    // parse sub_cmd
-                    self.parse_cmd(sub_cmd, args[(cursor_index + 1)..].to_vec())?
+                    // A subcommand cannot come after the arguments of its parent
+                    return Err(CmderError::unresolved_argument(vec![arg.clone()]));
                 }
-            } else if !self.is_marked(cursor_index) {
-                // check if any arguments were expected
-                let arg_cfg = self.parse_args(cmd.get_arguments(), args.clone())?;
-
-                if !arg_cfg.is_empty() {
-                    self.valid_arg_found = true;
-                    self.parser_cfg.arg_matches.extend_from_slice(&arg_cfg[..]);
-                    self.parser_cfg.matched_cmd = Some(cmd);
-                } else if !cmd.get_subcommands().is_empty() {
-                    // if no args were expected and the first arg is not empty, then it was probably a command
-                    return Err(CmderError::UnknownCommand(arg.clone()));
-                } else {
-                    // Otherwise, the argument is not valid and could not be resolved
-                    // TODO: return invalid ctx error
-                    return Err(CmderError::UnresolvedArgument(vec![arg.clone()]));
+            } else if !cmd.get_arguments().is_empty() {
+                self.positional_values.push((cursor_index, arg.clone()));
+            } else if !cmd.get_subcommands().is_empty() {
+                // if no args were expected and the arg is not empty, then it was probably a command
+                return Err(self.unknown_command(arg));
+            } else {
+                // Otherwise, the argument is not valid and could not be resolved
+                return Err(CmderError::unresolved_argument(vec![arg.clone()]));
+            }
+
+            cursor_index += 1;
+        }
+
+        self.parse_env_values()?;
+        self.parse_option_defaults();
+
+        let mut values = std::mem::take(&mut self.positional_values);
+        let positional_count = values.len();
+        values.append(&mut self.trailing_values);
+
+        let (arg_cfg, consumed) = self.parse_args(self.cmd.get_arguments(), &values)?;
+
+        // Trailing values that were not needed by any argument are passed through as positional args
+        let rest = values
+            .drain(consumed.max(positional_count)..)
+            .map(|(_, v)| v);
+        self.parser_cfg.positional_args.extend(rest);
+
+        // Only positional values passed before `--` can be left over at this point
+        if consumed < values.len() {
+            // Values left over by a variadic arg that reached its maximum
+            if let Some(last) = self.cmd.get_arguments().last().filter(|a| a.is_variadic()) {
+                let raw = last.get_raw_value();
+                if let Some(arg) = arg_cfg
+                    .iter()
+                    .find(|a| a.instance_of == raw && !a.is_default)
+                {
+                    let got = arg.values.len() + values.len() - consumed;
+                    let err = CmderError::wrong_number_of_values(&raw, last.get_num_values(), got);
+                    return Err(err);
                 }
             }
+
+            let vals = values[consumed..].iter().map(|(_, v)| v.clone()).collect();
+            return Err(CmderError::unresolved_argument(vals));
         }
 
+        self.parser_cfg.arg_matches.extend_from_slice(&arg_cfg[..]);
+        self.apply_conditional_defaults();
+
         if !self.parser_cfg.contains_flag("--help") {
-            for opt in self.cmd.get_options() {
-                if opt.required && !self.parser_cfg.contains_option(opt.short) {
-                    // TODO: Throw missing option error
-                    eprintln!("Missing required option!!!");
-                    std::process::exit(100);
+            self.check_required_options()?;
+            self.check_conditional_requirements()?;
+            self.check_relations()?;
+        }
+
+        Ok(self.parser_cfg.clone())
+    }
+
+    // Collects every required option that was not passed. Options whose values were read from the environment or from defaults are considered present
+    fn check_required_options(&self) -> CmderResult<()> {
+        let missing: Vec<String> = self
+            .cmd
+            .get_options()
+            .iter()
+            .filter(|opt| opt.is_required && !self.has_option_match(opt))
+            .map(|opt| match (opt.short.as_str(), opt.long.as_str()) {
+                ("", long) => long.to_string(),
+                (short, "") => short.to_string(),
+                (short, long) => format!("{short}, {long}"),
+            })
+            .collect();
+
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(CmderError::missing_required_option(missing))
+        }
+    }
+
+    // Applies the first matching `default_if` rule of any option or argument that was not passed
+    fn apply_conditional_defaults(&mut self) {
+        let cmd = self.cmd;
+
+        for opt in cmd.get_options() {
+            let id = display_name(&opt.long, &opt.short);
+            let arg = match opt.arguments.first() {
+                Some(a) if !self.parser_cfg.contains_option(&id) => a,
+                _ => continue,
+            };
+
+            if let Some(default) = self.find_conditional_default(&opt.default_if) {
+                let args = vec![ArgsMatches::from_default(arg.get_raw_value(), &default)];
+                let matches = &mut self.parser_cfg.option_matches;
+                matches.retain(|om| display_name(&om.option.long, &om.option.short) != id);
+                matches.push(OptionsMatches {
+                    appearance_count: 0,
+                    cursor_index: 0,
+                    option: opt.clone(),
+                    args,
+                    value_forms: vec![ValueForm::Default],
+                    occurrences: vec![],
+                });
+            }
+        }
+
+        for arg in cmd.get_arguments() {
+            let raw = arg.get_raw_value();
+            let matches = &self.parser_cfg.arg_matches;
+            if matches
+                .iter()
+                .any(|a| a.instance_of == raw && !a.is_default)
+            {
+                continue;
+            }
+
+            if let Some(default) = self.find_conditional_default(arg.get_default_if()) {
+                let matches = &mut self.parser_cfg.arg_matches;
+                matches.retain(|a| a.instance_of != raw);
+                matches.push(ArgsMatches::from_default(raw, &default));
+            }
+        }
+    }
+
+    fn find_conditional_default(&self, rules: &[(String, String, String)]) -> Option<String> {
+        rules
+            .iter()
+            .find(|(id, val, _)| self.has_value(id, val))
+            .map(|(_, _, default)| default.clone())
+    }
+
+    // Checks whether the option was matched, whether passed or through its environment variable or default values. Options are identified by their long name or, failing that, their short name
+    fn has_option_match(&self, opt: &CmderOption) -> bool {
+        let id = display_name(&opt.long, &opt.short);
+
+        self.parser_cfg
+            .option_matches
+            .iter()
+            .any(|om| display_name(&om.option.long, &om.option.short) == id)
+    }
+
+    // Checks the `required_if` and `required_unless` rules of the options and arguments of the matched command
+    fn check_conditional_requirements(&self) -> CmderResult<()> {
+        let cmd = self.cmd;
+
+        for opt in cmd.get_options() {
+            if !self.has_option_match(opt) {
+                let name = display_name(&opt.long, &opt.short);
+                self.check_rules(&opt.required_if, &opt.required_unless)
+                    .map_err(|rule| CmderError::conditionally_required_option(&name, &rule))?;
+            }
+        }
+
+        for arg in cmd.get_arguments() {
+            let raw = arg.get_raw_value();
+            let is_present = self
+                .parser_cfg
+                .arg_matches
+                .iter()
+                .any(|a| a.instance_of == raw);
+
+            if !is_present {
+                self.check_rules(arg.get_required_if(), arg.get_required_unless())
+                    .map_err(|rule| CmderError::conditionally_required_argument(&raw, &rule))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Returns a description of the first rule that makes a missing item required
+    fn check_rules(
+        &self,
+        required_if: &[(String, String)],
+        required_unless: &[String],
+    ) -> Result<(), String> {
+        if let Some((id, val)) = required_if.iter().find(|(id, val)| self.has_value(id, val)) {
+            return Err(format!("required if `{id}` is `{val}`"));
+        }
+
+        if !required_unless.is_empty() && !required_unless.iter().any(|id| self.is_passed(id)) {
+            let ids = required_unless
+                .iter()
+                .map(|id| format!("`{id}`"))
+                .collect::<Vec<_>>()
+                .join(" or ");
+            return Err(format!("required unless {ids} is passed"));
+        }
+
+        Ok(())
+    }
+
+    // Returns true if the given flag, option, option argument or argument has the given value in the final matches. Flags have the value `true` when switched on and `false` when negated
+    fn has_value(&self, id: &str, val: &str) -> bool {
+        let matches = &self.parser_cfg;
+
+        if let Some(state) = matches.get_flag_state(id) {
+            return state.to_string() == val;
+        }
+
+        let option_values = matches
+            .option_matches
+            .iter()
+            .filter(|om| om.contains_option(id))
+            .flat_map(|om| om.args.iter())
+            .chain(self.option_arg_matches(id));
+
+        let arg_values = self
+            .cmd
+            .get_arguments()
+            .iter()
+            .filter(|a| a.get_name() == id || a.get_raw_value() == id)
+            .flat_map(|a| {
+                let raw = a.get_raw_value();
+                matches
+                    .arg_matches
+                    .iter()
+                    .filter(move |m| m.instance_of == raw)
+            });
+
+        option_values
+            .chain(arg_values)
+            .any(|a| a.values.iter().any(|v| v == val))
+    }
+
+    // Returns true if the given flag, option, option argument or argument was passed, default values are not considered
+    fn is_passed(&self, id: &str) -> bool {
+        let matches = &self.parser_cfg;
+
+        matches.get_flag_state(id).is_some()
+            || matches.contains_option(id)
+            || self.option_arg_matches(id).any(|m| !m.is_default)
+            || self
+                .cmd
+                .get_arguments()
+                .iter()
+                .filter(|a| a.get_name() == id || a.get_raw_value() == id)
+                .any(|a| {
+                    let raw = a.get_raw_value();
+                    matches
+                        .arg_matches
+                        .iter()
+                        .any(|m| m.instance_of == raw && !m.is_default)
+                })
+    }
+
+    // Returns the matches of the option argument with the given name, i.e `<level>` or `level`
+    fn option_arg_matches<'m>(&'m self, id: &'m str) -> impl Iterator<Item = &'m ArgsMatches> {
+        self.parser_cfg.option_matches.iter().flat_map(move |om| {
+            let raw_values: Vec<String> = om
+                .option
+                .arguments
+                .iter()
+                .filter(|a| a.get_name() == id || a.get_raw_value() == id)
+                .map(|a| a.get_raw_value())
+                .collect();
+
+            om.args
+                .iter()
+                .filter(move |m| raw_values.contains(&m.instance_of))
+        })
+    }
+
+    // Validates the conflicts, requirements and argument groups of the flags, options and arguments of the matched command
+    fn check_relations(&self) -> CmderResult<()> {
+        let items = self.collect_items();
+        let find = |id: &str| items.iter().find(|i| i.ids.iter().any(|v| v == id));
+        let is_present = |id: &str| find(id).is_some_and(|i| i.is_present);
+        let display = |id: &str| find(id).map_or(id.to_string(), |i| i.display.clone());
+
+        for item in items.iter().filter(|i| i.is_present) {
+            if let Some(other) = item.conflicts_with.iter().find(|c| is_present(c)) {
+                return Err(CmderError::argument_conflict(
+                    &item.display,
+                    &display(other),
+                ));
+            }
+
+            if let Some(req) = item.requires.iter().find(|r| !is_present(r)) {
+                return Err(CmderError::missing_dependency(&item.display, &display(req)));
+            }
+        }
+
+        // Groups that are only referenced by their members behave like a default group
+        let mut groups = self.cmd.get_groups().clone();
+        for name in items.iter().flat_map(|i| i.groups.iter()) {
+            if !groups.iter().any(|g| &g.name == name) {
+                groups.push(ArgGroup::new(name));
+            }
+        }
+
+        for group in &groups {
+            let mut members: Vec<String> = group.members.iter().map(|m| display(m)).collect();
+            for item in items.iter().filter(|i| i.groups.contains(&group.name)) {
+                if !members.contains(&item.display) {
+                    members.push(item.display.clone());
                 }
             }
+
+            let present: Vec<String> = members.iter().filter(|m| is_present(m)).cloned().collect();
+            let is_valid = match group.policy {
+                GroupPolicy::ExactlyOne => present.len() == 1,
+                GroupPolicy::AtLeastOne => !present.is_empty(),
+                GroupPolicy::AtMostOne => present.len() <= 1,
+            };
+
+            if !is_valid {
+                let vals = if present.is_empty() { members } else { present };
+                return Err(CmderError::arg_group_violation(
+                    &group.name,
+                    group.policy,
+                    vals,
+                ));
+            }
         }
 
-        Ok(self.parser_cfg.clone())
+        Ok(())
     }
 
-    fn is_marked(&self, idx: usize) -> bool {
-        self.marked_args.get(idx).unwrap().1
+    // Gathers the flags, options and arguments of the matched command along with whether they were passed
+    fn collect_items(&self) -> Vec<RelatedItem<'p>> {
+        let cmd = self.cmd;
+        let matches = &self.parser_cfg;
+        let mut items = vec![];
+
+        for flag in cmd.get_flags() {
+            items.push(RelatedItem {
+                ids: vec![flag.long.clone(), flag.short.clone()],
+                display: display_name(&flag.long, &flag.short),
+                is_present: matches.contains_flag(&display_name(&flag.long, &flag.short)),
+                conflicts_with: &flag.conflicts_with,
+                requires: &flag.requires,
+                groups: &flag.groups,
+            });
+        }
+
+        for opt in cmd.get_options() {
+            items.push(RelatedItem {
+                ids: vec![opt.long.clone(), opt.short.clone()],
+                display: display_name(&opt.long, &opt.short),
+                is_present: matches.contains_option(&display_name(&opt.long, &opt.short)),
+                conflicts_with: &opt.conflicts_with,
+                requires: &opt.requires,
+                groups: &opt.groups,
+            });
+        }
+
+        for arg in cmd.get_arguments() {
+            let raw = arg.get_raw_value();
+            let is_present = matches
+                .arg_matches
+                .iter()
+                .any(|a| a.instance_of == raw && !a.is_default);
+
+            // Arguments can be referred to by either their name or their raw value, i.e `<file>`
+            items.push(RelatedItem {
+                ids: vec![arg.get_name().into(), raw.clone()],
+                display: raw,
+                is_present,
+                conflicts_with: arg.get_conflicts_with(),
+                requires: arg.get_requires(),
+                groups: arg.get_groups(),
+            });
+        }
+
+        items
     }
 
-    // Returns option matches
-    fn parse_option(&mut self, opt: CmderOption<'p>, args: Vec<String>) -> CmderResult<()> {
-        let count = self.parser_cfg.get_option_count(opt.short);
-        let args = self.parse_args(&opt.arguments, args)?;
-        let config = &mut self.parser_cfg;
+    // Checks whether an arg starting with a dash should be passed to the next positional argument of the command
+    fn is_positional_hyphen_value(&self, arg: &str) -> bool {
+        let args = self.cmd.get_arguments();
+        let next_arg = args
+            .get(self.positional_values.len())
+            .or_else(|| args.last().filter(|a| a.is_variadic()));
+
+        next_arg.is_some_and(|a| self.is_hyphen_value(arg, a.allows_hyphen_values()))
+    }
+
+    // Args starting with a dash are treated as values when they do not match any flag or option and they are either a lone `-`, passed to an argument that allows hyphen values, or negative numbers when the `AllowNegativeNumbers` setting is enabled
+    fn is_hyphen_value(&self, arg: &str, allow_hyphen: bool) -> bool {
+        let cmd = self.cmd;
+        let is_defined = |val: &str| {
+            resolve_flag(cmd.get_flags(), val.into(), self.policy()).is_some()
+                || resolve_option(cmd.get_options(), val.into(), self.policy()).is_some()
+                || resolve_negated_flag(cmd.get_flags(), val, self.policy()).is_some()
+        };
+
+        if arg == "--" || is_defined(arg) {
+            return false;
+        }
+
+        let is_negative_number = arg[1..].starts_with(|c: char| c.is_ascii_digit() || c == '.')
+            && arg[1..].parse::<f64>().is_ok()
+            && !is_defined(&arg[..2]);
+
+        arg == "-"
+            || allow_hyphen
+            || (is_negative_number && self.setting(Setting::AllowNegativeNumbers))
+    }
+
+    // Resolves a subcommand by its name or alias, or by a unique prefix of its name when abbreviations are allowed. Once positional values have been passed, only exact names are matched. Deprecated aliases are recorded so that their warnings can be emitted
+    fn find_subcommand(&mut self, arg: &str) -> CmderResult<Option<&'p Command<'p>>> {
+        let cmd = self.cmd;
+
+        if let Some(sub_cmd) = cmd.find_subcommand_with(arg, self.policy()) {
+            if let Some(warning) = sub_cmd.get_deprecation_warning(arg, self.policy()) {
+                let name = sub_cmd.get_name().to_string();
+                let entry = (arg.to_string(), name, warning);
+                self.parser_cfg.deprecated_aliases.push(entry);
+            }
+            return Ok(Some(sub_cmd));
+        }
+
+        if !self.allows_abbreviations() || !self.positional_values.is_empty() {
+            return Ok(None);
+        }
+
+        let names = cmd.get_subcommands().iter().map(|c| c.get_name());
+        let name = match_prefix(arg, names, self.policy())?;
 
-        if config.contains_option(opt.long) {
-            for opt_cfg in config.option_matches.iter_mut() {
-                if opt_cfg.option.long == opt.long {
-                    opt_cfg.args.extend_from_slice(&args[..]);
-                    opt_cfg.appearance_count += 1;
+        Ok(name.and_then(|n| cmd.find_subcommand_with(&n, self.policy())))
+    }
+
+    // Expands a unique prefix of a long flag or option, i.e `--verb` or `--verb=3` for `--verbose`, returning `None` if nothing matches. Flags cannot take an attached value, so `--verb=3` is an unknown option when `--verbose` is a flag
+    fn expand_abbreviation(&self, arg: &str) -> CmderResult<Option<String>> {
+        if !self.allows_abbreviations() {
+            return Ok(None);
+        }
+
+        let (key, value) = match arg.split_once('=') {
+            Some((key, value)) => (key, Some(value)),
+            None => (arg, None),
+        };
+
+        let names = self.long_names();
+        let expanded = match_prefix(key, names.iter().map(|n| n.as_str()), self.policy())?;
+
+        match (expanded, value) {
+            (Some(name), Some(v)) => {
+                if resolve_option(self.cmd.get_options(), name.clone(), self.policy()).is_none() {
+                    return Err(self.unknown_option(arg));
+                }
+                Ok(Some(format!("{name}={v}")))
+            }
+            (expanded, _) => Ok(expanded),
+        }
+    }
+
+    // Returns the long names of the flags and options of the command, including the negated forms of negatable flags
+    fn long_names(&self) -> Vec<String> {
+        let cmd = self.cmd;
+        let negated = cmd
+            .get_flags()
+            .iter()
+            .filter(|f| f.is_negatable)
+            .map(|f| format!("--no-{}", f.name));
+
+        cmd.get_flags()
+            .iter()
+            .map(|f| f.long.clone())
+            .chain(negated)
+            .chain(cmd.get_options().iter().map(|o| o.long.clone()))
+            .collect()
+    }
+
+    // Builds the error for an unknown flag or option, suggesting similar long names when a long name was passed
+    fn unknown_option(&self, arg: &str) -> CmderError {
+        let err = CmderError::unknown_option(arg);
+
+        if !arg.starts_with("--") {
+            return err;
+        }
+
+        let key = arg.split_once('=').map_or(arg, |(key, _)| key);
+        let names = self.long_names();
+        err.did_you_mean(suggest(
+            key,
+            names.iter().map(|n| n.as_str()),
+            self.policy(),
+        ))
+    }
+
+    // Builds the error for an unknown subcommand, suggesting subcommands with similar names
+    fn unknown_command(&self, arg: &str) -> CmderError {
+        let names = self.cmd.get_subcommands().iter().map(|c| c.get_name());
+        CmderError::unknown_command(arg).did_you_mean(suggest(arg, names, self.policy()))
+    }
+
+    fn cmd_names(&self) -> Vec<&str> {
+        self.cmd_path.iter().map(|c| c.get_name()).collect()
+    }
+
+    // A setting applies to the command it is set on and to all of its subcommands, so it is enabled if any of the commands matched so far has it
+    fn setting(&self, setting: Setting) -> bool {
+        self.cmd_path.iter().any(|c| c.get_setting(setting.clone()))
+    }
+
+    fn policy(&self) -> MatchPolicy {
+        MatchPolicy {
+            ignore_case: self.setting(Setting::IgnoreCase),
+            ignore_separators: self.setting(Setting::IgnoreSeparators),
+        }
+    }
+
+    fn allows_abbreviations(&self) -> bool {
+        self.setting(Setting::AllowAbbreviations)
+    }
+
+    // Resolves any arg starting with a dash and returns the number of args following it that were consumed
+    fn parse_dash_arg(&mut self, arg: &str, rest: &[String]) -> CmderResult<usize> {
+        let cmd = self.cmd;
+
+        if let Some(flag) = resolve_flag(cmd.get_flags(), arg.into(), self.policy()) {
+            self.parse_flag(flag, false)?;
+            Ok(0)
+        } else if let Some(opt) = resolve_option(cmd.get_options(), arg.into(), self.policy()) {
+            self.parse_option(opt, None, rest)
+        } else if let Some(flag) = resolve_negated_flag(cmd.get_flags(), arg, self.policy()) {
+            self.parse_flag(flag, true)?;
+            Ok(0)
+        } else if arg.starts_with("--") {
+            // Split the arg into key and value on the first `=` only, i.e `--define=KEY=VALUE`
+            let attached = arg.split_once('=').and_then(|(key, value)| {
+                resolve_option(cmd.get_options(), key.into(), self.policy()).map(|opt| (opt, value))
+            });
+
+            match attached {
+                Some((opt, value)) => {
+                    self.parse_option(opt, Some((value.into(), ValueForm::Equals)), rest)
                 }
+                None => match self.expand_abbreviation(arg)? {
+                    // Only recurse on an actual expansion, an arg that expands to itself is not known
+                    Some(expanded) if expanded != arg => self.parse_dash_arg(&expanded, rest),
+                    _ => Err(self.unknown_option(arg)),
+                },
+            }
+        } else if arg.len() > 2 {
+            self.parse_short_cluster(arg, rest)
+        } else {
+            Err(self.unknown_option(arg))
+        }
+    }
+
+    // Expands a cluster of short flags such as `-abc` into `-a -b -c`. If one of the letters is an option, the rest of the cluster(or the args that follow) are used as its values. A value can also be attached to a short option directly(`-p8080`) or with an `=`(`-p=8080`)
+    fn parse_short_cluster(&mut self, arg: &str, rest: &[String]) -> CmderResult<usize> {
+        let cmd = self.cmd;
+        let letters = &arg[1..];
+
+        for (idx, ch) in letters.char_indices() {
+            let short = format!("-{ch}");
+
+            if let Some(flag) = resolve_flag(cmd.get_flags(), short.clone(), self.policy()) {
+                self.parse_flag(flag, false)?;
+            } else if let Some(opt) =
+                resolve_option(cmd.get_options(), short.clone(), self.policy())
+            {
+                let remaining = &letters[(idx + ch.len_utf8())..];
+                let attached = if let Some(value) = remaining.strip_prefix('=') {
+                    Some((value.to_string(), ValueForm::Equals))
+                } else if !remaining.is_empty() {
+                    Some((remaining.to_string(), ValueForm::Glued))
+                } else {
+                    None
+                };
+
+                return self.parse_option(opt, attached, rest);
+            } else {
+                return Err(self.unknown_option(&short));
+            }
+        }
+
+        Ok(0)
+    }
+
+    // Parses an option along with its arguments and returns the number of args following the option that were consumed
+    fn parse_option(
+        &mut self,
+        opt: CmderOption,
+        attached: Option<(String, ValueForm)>,
+        rest: &[String],
+    ) -> CmderResult<usize> {
+        let cursor_index = self.cursor_index;
+        let attached_count = usize::from(attached.is_some());
+        let mut value_form = ValueForm::Separate;
+        let mut values = vec![];
+
+        if let Some((val, form)) = attached {
+            values.push((cursor_index, val));
+            value_form = form;
+        }
+
+        // Options whose arguments are all optional only take attached values so that they don't swallow the positional args following them
+        let takes_separate_values = opt.arguments.iter().any(|a| a.is_required());
+
+        let allow_hyphen = opt.arguments.iter().any(|a| a.allows_hyphen_values());
+
+        for (i, val) in rest.iter().enumerate() {
+            let is_value = !val.starts_with('-') || self.is_hyphen_value(val, allow_hyphen);
+            if !is_value || !takes_separate_values || is_help_flag(val) {
+                break;
+            }
+            values.push((cursor_index + i + 1, val.clone()));
+        }
+
+        if values.is_empty() {
+            value_form = ValueForm::Omitted;
+        }
+
+        let parsed = match (opt.value_delimiter, &opt.arguments[..]) {
+            // A delimited option takes a single value per occurrence which is split into multiple values. Delimiters are ignored by options taking several arguments
+            (Some(delim), [arg]) if !values.is_empty() => {
+                split_option_value(arg, &values[0], delim, self.policy()).map(|args| (args, 1))
+            }
+            _ => self.parse_args(&opt.arguments, &values),
+        };
+
+        // An option missing its values right before the help flag does not prevent help from being shown, i.e `start -p --help`
+        let separate_count = values.len() - attached_count;
+        if parsed.is_err() && rest.get(separate_count).is_some_and(|v| is_help_flag(v)) {
+            return Ok(separate_count);
+        }
+
+        let (args, consumed) = match parsed {
+            Err(e) if e.get_kind() == Event::MissingRequiredArgument && values.is_empty() => {
+                return Err(CmderError::option_missing_argument(&e.args[0], &opt.long));
             }
+            Err(e)
+                if e.get_kind() == Event::MissingRequiredArgument
+                    || e.get_kind() == Event::WrongNumberOfValues =>
+            {
+                let range = get_option_num_values(&opt);
+                let err = CmderError::wrong_number_of_values(&opt.long, range, values.len());
+                return Err(err);
+            }
+            res => res?,
+        };
+
+        if opt.is_key_value {
+            self.check_key_value_pairs(&opt, &args)?;
+        }
+
+        let config = &mut self.parser_cfg;
+        let occurrence = args.iter().flat_map(|a| a.values.clone()).collect();
+        let id = display_name(&opt.long, &opt.short);
+
+        if let Some(opt_cfg) = config
+            .option_matches
+            .iter_mut()
+            .find(|om| display_name(&om.option.long, &om.option.short) == id)
+        {
+            opt_cfg.args.extend_from_slice(&args[..]);
+            opt_cfg.value_forms.push(value_form);
+            opt_cfg.occurrences.push(occurrence);
+            opt_cfg.appearance_count += 1;
         } else {
             let opt_cfg = OptionsMatches {
-                appearance_count: (count + 1) as usize,
-                cursor_index: self.cursor_index,
+                appearance_count: 1,
+                cursor_index,
                 option: opt,
                 args,
+                value_forms: vec![value_form],
+                occurrences: vec![occurrence],
             };
 
             config.option_matches.push(opt_cfg);
         }
 
+        // The attached value is part of the option arg itself
+        Ok(consumed.saturating_sub(attached_count))
+    }
+
+    // Rejects malformed pairs passed to key-value options, as well as repeated keys if duplicate keys are not allowed
+    fn check_key_value_pairs(&self, opt: &CmderOption, args: &[ArgsMatches]) -> CmderResult<()> {
+        let id = display_name(&opt.long, &opt.short);
+        let mut keys: Vec<String> = self
+            .parser_cfg
+            .option_matches
+            .iter()
+            .filter(|om| display_name(&om.option.long, &om.option.short) == id)
+            .filter(|om| om.appearance_count > 0)
+            .flat_map(|om| om.args.iter().flat_map(|a| a.values.iter()))
+            .filter_map(|v| v.split_once('=').map(|(k, _)| k.to_string()))
+            .collect();
+
+        for arg in args {
+            for val in &arg.values {
+                let key = match val.split_once('=') {
+                    Some((key, _)) if !key.is_empty() => key,
+                    _ => {
+                        let reason = "expected a key=value pair";
+                        return Err(CmderError::invalid_argument_value(
+                            &arg.instance_of,
+                            val,
+                            reason,
+                        ));
+                    }
+                };
+
+                if !opt.allow_duplicate_keys && keys.iter().any(|k| k == key) {
+                    let reason = format!("the key `{key}` has already been passed");
+                    return Err(CmderError::invalid_argument_value(
+                        &arg.instance_of,
+                        val,
+                        &reason,
+                    ));
+                }
+
+                keys.push(key.to_string());
+            }
+        }
+
         Ok(())
     }
 
-    // Returns flag matches
-    fn parse_flag(&mut self, flag: CmderFlag<'p>) {
+    // Returns flag matches. Repeated occurrences are counted while for negatable flags, the last occurrence determines whether the flag is on or off. Negating a flag resets its count
+    fn parse_flag(&mut self, flag: CmderFlag, negated: bool) -> CmderResult<()> {
         // TODO: Check if context is valid for flag position
-        let cfg = FlagsMatches {
-            appearance_count: 1,
-            cursor_index: self.cursor_index,
-            flag,
+        let flag_matches = &mut self.parser_cfg.flag_matches;
+        let count = usize::from(!negated);
+        let id = display_name(&flag.long, &flag.short);
+
+        let cfg = match flag_matches
+            .iter_mut()
+            .find(|f| display_name(&f.flag.long, &f.flag.short) == id)
+        {
+            Some(cfg) => {
+                cfg.is_negated = negated;
+                cfg.appearance_count = if negated { 0 } else { cfg.appearance_count + 1 };
+                cfg
+            }
+            None => {
+                flag_matches.push(FlagsMatches {
+                    appearance_count: count,
+                    cursor_index: self.cursor_index,
+                    is_negated: negated,
+                    flag,
+                });
+                flag_matches.last_mut().unwrap()
+            }
         };
 
-        if !self.parser_cfg.contains_flag(cfg.flag.short) {
-            self.parser_cfg.flag_matches.push(cfg);
+        match cfg.flag.max_occurrences {
+            Some(max) if cfg.appearance_count > max => {
+                Err(CmderError::too_many_occurrences(&id, max))
+            }
+            _ => Ok(()),
         }
     }
 
-    // Parse subcmds
-    fn parse_cmd(&mut self, cmd: &'p Command<'p>, args: Vec<String>) -> CmderResult<()> {
-        self.cursor_index += 1;
-        self.parser_cfg.matched_cmd = Some(cmd);
-        self.cmd = cmd;
+    // Falls back to environment variables for any flags and options of the matched command that were not passed
+    fn parse_env_values(&mut self) -> CmderResult<()> {
+        let cmd = self.cmd;
 
-        for (i, a) in args.iter().enumerate() {
-            if let Some(sc) = cmd.find_subcommand(a) {
-                self.marked_args[(self.cursor_index + i)].1 = true;
-                return self.parse_cmd(sc, args[(i + 1)..].to_vec());
+        for flag in cmd.get_flags() {
+            let id = display_name(&flag.long, &flag.short);
+            if self.parser_cfg.get_flag_state(&id).is_some() {
+                continue;
             }
-        }
-        let arg_cfg = self.parse_args(cmd.get_arguments(), args)?;
 
-        if !arg_cfg.is_empty() {
-            self.valid_arg_found = true;
+            if let Some((var, val)) = read_env(flag.env.as_deref()) {
+                match parse_bool_value(&val) {
+                    Some(state) => self.parse_flag(flag.clone(), !state)?,
+                    None => {
+                        let reason = "expected one of: 1, 0, true, false, yes, no";
+                        return Err(CmderError::invalid_argument_value(&var, &val, reason));
+                    }
+                }
+            }
         }
 
-        self.parser_cfg.arg_matches.extend_from_slice(&arg_cfg[..]);
+        for opt in cmd.get_options() {
+            if self
+                .parser_cfg
+                .contains_option(&display_name(&opt.long, &opt.short))
+            {
+                continue;
+            }
+
+            if let Some((_, val)) = read_env(opt.env.as_deref()) {
+                self.parse_option(opt.clone(), Some((val, ValueForm::Env)), &[])?;
+            }
+        }
 
         Ok(())
     }
 
-    // Parse arg
+    // Records the default values of the arguments of any options that were not passed
+    fn parse_option_defaults(&mut self) {
+        let cmd = self.cmd;
+
+        for opt in cmd.get_options() {
+            if self
+                .parser_cfg
+                .contains_option(&display_name(&opt.long, &opt.short))
+            {
+                continue;
+            }
+
+            let args = opt
+                .arguments
+                .iter()
+                .filter_map(|a| {
+                    let val = a.get_default_value()?;
+                    Some(ArgsMatches::from_default(a.get_raw_value(), val))
+                })
+                .collect::<Vec<_>>();
+
+            if !args.is_empty() {
+                self.parser_cfg.option_matches.push(OptionsMatches {
+                    appearance_count: 0,
+                    cursor_index: 0,
+                    option: opt.clone(),
+                    args,
+                    value_forms: vec![ValueForm::Default],
+                    occurrences: vec![],
+                });
+            }
+        }
+    }
+
+    // Parse subcmds
+    fn parse_cmd(&mut self, cmd: &'p Command<'p>) {
+        self.cmd_path.push(cmd);
+        self.parser_cfg.matched_cmd = Some(cmd);
+        self.cmd = cmd;
+    }
+
+    // Maps the values provided onto the list of arguments, returning the argument matches and the number of values consumed
     fn parse_args(
         &mut self,
         arg_list: &[Argument],
-        raw_args: Vec<String>,
-    ) -> CmderResult<Vec<ArgsMatches>> {
-        let cursor_index = self.cursor_index;
-        let max_args_len = arg_list.len();
+        values: &[(usize, String)],
+    ) -> CmderResult<(Vec<ArgsMatches>, usize)> {
         let mut arg_vec = vec![];
+        let mut consumed = 0;
 
-        for (arg_idx, arg_val) in arg_list.iter().enumerate() {
-            let step = arg_idx + 1;
-            let mut raw_value = String::new();
+        for (idx, arg_val) in arg_list.iter().enumerate() {
+            let remaining = &values[consumed..];
+            let (min, max) = arg_val.get_num_values();
 
-            // check if arg is variadic
-            if arg_val.variadic {
-                for (i, val) in raw_args.iter().enumerate() {
-                    let full_idx = cursor_index + i;
-                    if !val.starts_with('-') && !self.is_marked(full_idx) {
-                        self.marked_args[full_idx].1 = true;
+            // Values are reserved for the required args that follow so that a variadic or optional arg does not starve them, i.e `cp <src...> <dest>`. A required arg still gets the values it needs first
+            let reserved: usize = arg_list[(idx + 1)..]
+                .iter()
+                .filter(|a| a.is_required())
+                .map(|a| a.get_num_values().0)
+                .sum();
+            let mut available = remaining.len().saturating_sub(reserved);
+            if arg_val.is_required() {
+                available = available.max(min.min(remaining.len()));
+            }
 
-                        raw_value.push_str(val);
-                        raw_value.push(' ');
-                    }
-                }
-            } else if arg_idx <= max_args_len {
-                // valid to collect arguments
-                match raw_args.iter().enumerate().next() {
-                    Some((idx, val)) => {
-                        let full_index = cursor_index + idx;
-                        if !self.is_marked(full_index) && !val.starts_with('-') {
-                            self.marked_args[full_index].1 = true;
-                            raw_value.push_str(val)
-                        } else if val == "-h" || val == "--help" {
-                            break;
-                        } else if arg_val.required {
-                            // return err: expected one value found another
-                            let vals = vec![arg_val.literal.clone()];
-                            return Err(CmderError::MissingRequiredArgument(vals));
-                        } else {
-                            continue;
-                        }
-                    }
-                    None => {
-                        if arg_val.required {
-                            let vals = vec![arg_val.literal.clone()];
-                            return Err(CmderError::MissingRequiredArgument(vals));
-                        }
-                    }
+            if available == 0 {
+                let instance_of = arg_val.get_raw_value();
+
+                if let Some((_, val)) = read_env(arg_val.get_env()) {
+                    let val = arg_val.validate(&val, self.policy())?;
+                    let arg_cfg =
+                        ArgsMatches::with_values(self.cursor_index, instance_of, vec![val]);
+                    arg_vec.push(arg_cfg);
+                } else if let Some(val) = arg_val.get_default_value() {
+                    arg_vec.push(ArgsMatches::from_default(instance_of, val));
+                } else if arg_val.is_required() && !self.parser_cfg.contains_flag("--help") {
+                    let vals = vec![arg_val.get_raw_value()];
+                    return Err(CmderError::missing_required_argument(vals));
                 }
+                continue;
             }
 
-            let arg_cfg = ArgsMatches {
-                cursor_index: (cursor_index + step),
-                instance_of: arg_val.literal.clone(),
-                raw_value: raw_value.trim().to_string(),
-            };
+            // Variadic args take as many values as they can, up to their maximum
+            let taken = &remaining[..max.unwrap_or(usize::MAX).min(available)];
+
+            if taken.len() < min {
+                let raw = arg_val.get_raw_value();
+                return Err(CmderError::wrong_number_of_values(
+                    &raw,
+                    (min, max),
+                    taken.len(),
+                ));
+            }
+
+            // An arg with a maximum of 0 values never takes any
+            if taken.is_empty() {
+                continue;
+            }
+
+            let mut tokens = vec![];
+            for (idx, val) in taken {
+                tokens.push((*idx, arg_val.validate(val, self.policy())?));
+            }
+
+            arg_vec.push(ArgsMatches::from_tokens(arg_val.get_raw_value(), &tokens));
+
+            consumed += taken.len();
+        }
+
+        Ok((arg_vec, consumed))
+    }
+}
+
+// Finds the only name starting with the given prefix. Fails if the prefix matches more than one name
+fn match_prefix<'a>(
+    prefix: &str,
+    names: impl Iterator<Item = &'a str>,
+    policy: MatchPolicy,
+) -> CmderResult<Option<String>> {
+    let normalized = policy.normalize(prefix);
+    let mut candidates = names
+        .filter(|n| !n.is_empty() && policy.normalize(n).starts_with(&normalized))
+        .map(String::from)
+        .collect::<Vec<_>>();
+    candidates.dedup();
+
+    match candidates.len() {
+        0 => Ok(None),
+        1 => Ok(candidates.pop()),
+        _ => Err(CmderError::ambiguous_argument(prefix, candidates)),
+    }
+}
+
+// Splits a delimited option value into separate argument matches. An escaped delimiter, i.e `a\,b`, is kept as part of the value
+fn split_option_value(
+    arg: &Argument,
+    (cursor_index, val): &(usize, String),
+    delim: char,
+    policy: MatchPolicy,
+) -> CmderResult<Vec<ArgsMatches>> {
+    let mut parts = vec![];
+    let mut current = String::new();
+    let mut chars = val.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == '\\' && chars.peek() == Some(&delim) {
+            current.push(delim);
+            chars.next();
+        } else if c == delim {
+            parts.push(std::mem::take(&mut current));
+        } else {
+            current.push(c);
+        }
+    }
+    parts.push(current);
+
+    parts
+        .into_iter()
+        .map(|part| {
+            let part = arg.validate(&part, policy)?;
+            Ok(ArgsMatches::from_tokens(
+                arg.get_raw_value(),
+                &[(*cursor_index, part)],
+            ))
+        })
+        .collect()
+}
+
+// Checks whether a value is one of the built-in help flags, which are never consumed as the value of an option
+fn is_help_flag(val: &str) -> bool {
+    val == "-h" || val == "--help"
+}
+
+// The total number of values taken by an option, summed across its required arguments
+fn get_option_num_values(opt: &CmderOption) -> (usize, Option<usize>) {
+    let mut min = 0;
+    let mut max = Some(0);
+
+    for arg in &opt.arguments {
+        let (arg_min, arg_max) = arg.get_num_values();
+        if arg.is_required() {
+            min += arg_min;
+        }
+        max = max.zip(arg_max).map(|(a, b)| a + b);
+    }
+
+    (min, max)
+}
+
+// A flag, option or argument of the matched command along with its relations to other items
+struct RelatedItem<'a> {
+    ids: Vec<String>,
+    display: String,
+    is_present: bool,
+    conflicts_with: &'a [String],
+    requires: &'a [String],
+    groups: &'a [String],
+}
+
+// The name shown for a flag or option in errors, the long name is preferred. It also identifies the flag or option in the matches, since flags and options with only a short name share an empty long name
+fn display_name(long: &str, short: &str) -> String {
+    if long.is_empty() {
+        short.into()
+    } else {
+        long.into()
+    }
+}
+
+// Returns the name and value of the environment variable if it is set and not empty
+fn read_env(var: Option<&str>) -> Option<(String, String)> {
+    let var = var?;
+
+    std::env::var(var)
+        .ok()
+        .filter(|v| !v.is_empty())
+        .map(|v| (var.to_string(), v))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::formatter::FormatGenerator;
+
+    fn create_tar_cmd() -> Command<'static> {
+        let mut cmd = Command::new("tar");
+
+        cmd.option("-x --extract", "Extract files from an archive")
+            .option("-v --verbose", "Verbosely list files processed")
+            .option("-f --file <archive>", "Use the given archive file");
+
+        cmd
+    }
+
+    fn parse<'a>(cmd: &'a Command<'a>, args: &[&str]) -> CmderResult<ParserMatches<'a>> {
+        let args = args.iter().map(|a| a.to_string()).collect();
+        Parser::new(cmd).parse(args)
+    }
+
+    #[test]
+    fn test_short_flag_clusters() {
+        let cmd = create_tar_cmd();
+
+        let m = parse(&cmd, &["-xv"]).unwrap();
+        assert!(m.contains_flag("--extract"));
+        assert!(m.contains_flag("--verbose"));
+        assert!(!m.contains_option("--file"));
+
+        let m = parse(&cmd, &["-xvfarchive.tar"]).unwrap();
+        assert!(m.contains_flag("-x"));
+        assert!(m.contains_flag("-v"));
+        assert_eq!(m.get_option_arg("<archive>"), Some("archive.tar".into()));
+
+        let m = parse(&cmd, &["-vxf", "archive.tar"]).unwrap();
+        assert!(m.contains_flag("-x"));
+        assert_eq!(m.get_option_arg("<archive>"), Some("archive.tar".into()));
+
+        let err = parse(&cmd, &["-xzv"]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::UnknownOption);
+        assert_eq!(err.get_token(), Some("-z"));
+
+        let err = parse(&cmd, &["-xf"]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::OptionMissingArgument);
+
+        // Flags with only a short name are told apart by it
+        let mut cmd = Command::new("ls");
+        cmd.flag("-a", "Show hidden entries")
+            .flag("-b", "Escape special characters");
+
+        for args in [&["-a", "-b"][..], &["-ab"]] {
+            let m = parse(&cmd, args).unwrap();
+            assert!(m.contains_flag("-a"));
+            assert!(m.contains_flag("-b"));
+            assert_eq!(m.get_flag_count("-a"), 1);
+        }
+    }
+
+    #[test]
+    fn test_attached_option_values() {
+        let mut cmd = Command::new("build");
+        cmd.option("-p --port <port>", "The port to use")
+            .option("-D --define <definition>", "Define a variable");
+
+        let m = parse(&cmd, &["--define=KEY=VALUE", "-DNAME=a=b"]).unwrap();
+        assert_eq!(
+            m.get_instances_of("<definition>"),
+            vec!["KEY=VALUE", "NAME=a=b"]
+        );
+        assert_eq!(
+            m.get_value_forms("-D"),
+            vec![ValueForm::Equals, ValueForm::Glued]
+        );
+
+        for (args, form) in [
+            (vec!["-p8080"], ValueForm::Glued),
+            (vec!["-p=8080"], ValueForm::Equals),
+            (vec!["--port=8080"], ValueForm::Equals),
+            (vec!["--port", "8080"], ValueForm::Separate),
+        ] {
+            let m = parse(&cmd, &args).unwrap();
+            assert_eq!(m.get_option_arg("<port>"), Some("8080".into()));
+            assert_eq!(m.get_value_forms("--port"), vec![form]);
+        }
+
+        assert_eq!(
+            parse(&cmd, &["--unknown=8080"]).unwrap_err().get_kind(),
+            Event::UnknownOption
+        );
+    }
+
+    #[test]
+    fn test_negatable_flags() {
+        let mut cmd = Command::new("ls");
+        cmd.add_flag(CmderFlag::new("color").short('c').negatable(true))
+            .option("-a --all", "Show hidden files");
+
+        let m = parse(&cmd, &["--color"]).unwrap();
+        assert_eq!(m.get_flag_state("--color"), Some(true));
+        assert!(m.contains_flag("-c"));
+
+        let m = parse(&cmd, &["--color", "--no-color"]).unwrap();
+        assert_eq!(m.get_flag_state("--color"), Some(false));
+        assert!(!m.contains_flag("--color"));
+
+        let m = parse(&cmd, &["--no-color", "-a", "-c"]).unwrap();
+        assert_eq!(m.get_flag_state("--color"), Some(true));
+
+        let m = parse(&cmd, &["-a"]).unwrap();
+        assert_eq!(m.get_flag_state("--color"), None);
+
+        // Only flags marked as negatable accept the negated form
+        assert_eq!(
+            parse(&cmd, &["--no-all"]).unwrap_err().get_kind(),
+            Event::UnknownOption
+        );
+    }
+
+    #[test]
+    fn test_typed_values() {
+        let mut cmd = Command::new("sum");
+        cmd.argument("<numbers...>", "The numbers to add")
+            .option("-p --precision <digits>", "Digits after the decimal point");
+
+        let m = parse(&cmd, &["1", "2.5", "-p", "2"]).unwrap();
+        assert_eq!(m.get_value::<u8>("<digits>"), Some(2));
+        assert_eq!(m.get_values::<f64>("<numbers...>"), vec![1.0, 2.5]);
+        assert_eq!(m.try_get_value::<u8>("<missing>").unwrap(), None);
+
+        let err = m.try_get_values::<u32>("<numbers...>").unwrap_err();
+        assert_eq!(err.get_args()[0], "<numbers...>");
+        assert_eq!(err.get_token(), Some("2.5"));
+
+        let m = parse(&cmd, &["1", "-p", "two"]).unwrap();
+        let err = m.try_get_value::<u8>("<digits>").unwrap_err();
+        assert_eq!(err.get_kind(), Event::InvalidArgumentValue);
+        assert_eq!(err.get_args()[1], "two");
+    }
+
+    #[test]
+    fn test_counted_flags() {
+        let mut cmd = Command::new("ssh");
+        cmd.add_flag(
+            CmderFlag::new("verbose")
+                .short('v')
+                .negatable(true)
+                .max_occurrences(3),
+        )
+        .option("-q --quiet", "Quiet mode");
+
+        let m = parse(&cmd, &["-v", "-v", "-v"]).unwrap();
+        assert_eq!(m.get_flag_count("--verbose"), 3);
+
+        let m = parse(&cmd, &["-vqv"]).unwrap();
+        assert_eq!(m.get_flag_count("-v"), 2);
+        assert_eq!(m.get_flag_count("-q"), 1);
+        assert_eq!(m.get_flag_count("--missing"), 0);
+
+        let m = parse(&cmd, &["-vv", "--no-verbose", "-v"]).unwrap();
+        assert_eq!(m.get_flag_count("-v"), 1);
+
+        assert_eq!(
+            parse(&cmd, &["-vv", "-vv"]).unwrap_err().get_kind(),
+            Event::TooManyOccurrences
+        );
+
+        // Short-only flags are counted separately
+        let mut cmd = Command::new("ps");
+        cmd.add_flag(crate::parse::flags::new_flag("-d", "Debug").max_occurrences(2))
+            .flag("-e", "Every process");
+
+        let m = parse(&cmd, &["-ded", "-e"]).unwrap();
+        assert_eq!(m.get_flag_count("-d"), 2);
+        assert_eq!(m.get_flag_count("-e"), 2);
+
+        let err = parse(&cmd, &["-e", "-ddd"]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::TooManyOccurrences);
+        assert_eq!(err.get_args(), vec!["-d", "2"]);
+    }
+
+    #[test]
+    fn test_env_fallback() {
+        std::env::set_var("CMDER_TEST_PORT", "9000");
+        std::env::set_var("CMDER_TEST_DEBUG", "yes");
+        std::env::set_var("CMDER_TEST_QUIET", "maybe");
+        std::env::set_var("CMDER_TEST_TARGET", "prod");
+
+        let mut cmd = Command::new("deploy");
+        cmd.add_argument(Argument::new("<target>").env("CMDER_TEST_TARGET"))
+            .add_option(
+                CmderOption::new("port")
+                    .argument("<port>")
+                    .env("CMDER_TEST_PORT"),
+            )
+            .add_flag(CmderFlag::new("debug").env("CMDER_TEST_DEBUG"))
+            .add_flag(CmderFlag::new("color").env("CMDER_TEST_UNSET"));
+
+        let m = parse(&cmd, &[]).unwrap();
+        assert_eq!(m.get_arg("<target>"), Some("prod".into()));
+        assert_eq!(m.get_value::<u16>("<port>"), Some(9000));
+        assert_eq!(m.get_value_forms("--port"), vec![ValueForm::Env]);
+        assert!(m.contains_flag("--debug"));
+        assert_eq!(m.get_flag_state("--color"), None);
+
+        // Values passed on the command line take precedence
+        let m = parse(&cmd, &["staging", "--port", "80"]).unwrap();
+        assert_eq!(m.get_arg("<target>"), Some("staging".into()));
+        assert_eq!(m.get_value::<u16>("<port>"), Some(80));
+
+        let mut cmd = Command::new("quiet");
+        cmd.add_flag(CmderFlag::new("quiet").env("CMDER_TEST_QUIET"));
+
+        assert_eq!(
+            parse(&cmd, &[]).unwrap_err().get_kind(),
+            Event::InvalidArgumentValue
+        );
+
+        // Passing another short-only flag or option does not hide the environment fallback
+        std::env::set_var("CMDER_TEST_SHORT_FLAG", "1");
+        std::env::set_var("CMDER_TEST_SHORT_OPT", "x");
+        let mut cmd = Command::new("short");
+        cmd.flag("-a", "Some flag")
+            .option("-o <out>", "Some option")
+            .add_flag(crate::parse::flags::new_flag("-s", "").env("CMDER_TEST_SHORT_FLAG"))
+            .add_option(
+                crate::parse::options::new_option("-e <env>", "", false)
+                    .env("CMDER_TEST_SHORT_OPT"),
+            );
+
+        let m = parse(&cmd, &["-a", "-o", "y"]).unwrap();
+        assert!(m.contains_flag("-s"));
+        assert_eq!(m.get_option_arg("<env>"), Some("x".into()));
+    }
+
+    #[test]
+    fn test_arg_defaults_and_validation() {
+        let mut cmd = Command::new("serve");
+        cmd.add_argument(
+            Argument::new("[mode]")
+                .valid_values(vec!["dev", "prod"])
+                .default("dev"),
+        )
+        .add_option(CmderOption::new("port").add_argument(
+            Argument::new("<port>").default("8080").validate_with(|v| {
+                v.parse::<u16>()
+                    .map(|_| ())
+                    .map_err(|_| "not a valid port".into())
+            }),
+        ));
+
+        let m = parse(&cmd, &[]).unwrap();
+        assert_eq!(m.get_arg("[mode]"), Some("dev".into()));
+        assert_eq!(m.get_option_arg("<port>"), Some("8080".into()));
+        assert_eq!(m.get_value_forms("--port"), vec![ValueForm::Default]);
+        assert!(!m.contains_option("--port"));
+
+        let m = parse(&cmd, &["prod", "--port", "80"]).unwrap();
+        assert_eq!(m.get_arg("[mode]"), Some("prod".into()));
+        assert_eq!(m.get_option_arg("<port>"), Some("80".into()));
+        assert!(m.contains_option("--port"));
+
+        let err = parse(&cmd, &["test"]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::InvalidArgumentValue);
+        assert_eq!(
+            err.to_string(),
+            "Invalid value `test` for argument `[mode]`: expected one of: dev, prod"
+        );
+
+        assert_eq!(
+            parse(&cmd, &["--port", "port"]).unwrap_err().get_kind(),
+            Event::InvalidArgumentValue
+        );
+
+        // Passing one short-only option does not drop the defaults of another
+        let mut cmd = Command::new("short");
+        cmd.option("-o <out>", "Some option").add_option(
+            crate::parse::options::new_option("-l", "", false)
+                .add_argument(Argument::new("<level>").default("3")),
+        );
+
+        let m = parse(&cmd, &["-o", "x"]).unwrap();
+        assert_eq!(m.get_option_arg("<level>"), Some("3".into()));
+    }
+
+    #[test]
+    fn test_required_options() {
+        let mut cmd = Command::new("deploy");
+        cmd.required_option("-r --region <region>", "The region to deploy to")
+            .add_option(CmderOption::new("token").required(true).argument("<token>"))
+            .add_option(
+                CmderOption::new("replicas")
+                    .required(true)
+                    .add_argument(Argument::new("<count>").default("1")),
+            );
+
+        let err = parse(&cmd, &[]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::MissingRequiredOption);
+        assert_eq!(err.get_args(), vec!["-r, --region", "--token"]);
+        assert_eq!(
+            err.to_string(),
+            "Missing the following required option(s): `-r, --region` `--token`"
+        );
+
+        let err = parse(&cmd, &["-r", "eu"]).unwrap_err();
+        assert_eq!(err.get_args(), vec!["--token"]);
+
+        // Long-only required options are matched by their long name
+        let m = parse(&cmd, &["--region", "eu", "--token", "abc"]).unwrap();
+        assert_eq!(m.get_option_arg("<token>"), Some("abc".into()));
+        assert_eq!(m.get_option_arg("<count>"), Some("1".into()));
+
+        assert!(parse(&cmd, &["--help"]).is_ok());
+
+        // Short-only required options are matched by their short name
+        let mut cmd = Command::new("b");
+        cmd.required_option("-x <v>", "The first value")
+            .required_option("-y <w>", "The second value");
+
+        let err = parse(&cmd, &["-x", "1"]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::MissingRequiredOption);
+        assert_eq!(err.get_args(), vec!["-y"]);
+        assert!(parse(&cmd, &["-x", "1", "-y", "2"]).is_ok());
+    }
+
+    #[test]
+    fn test_conflicts_and_requires() {
+        let mut cmd = Command::new("export");
+        cmd.add_flag(CmderFlag::new("json").conflicts_with("--table"))
+            .flag("-t --table", "Output a table")
+            .add_option(
+                CmderOption::new("key")
+                    .argument("<path>")
+                    .requires("--cert"),
+            )
+            .add_option(CmderOption::new("cert").argument("<path>"))
+            .add_argument(Argument::new("[dest]").conflicts_with("-t"));
+
+        assert!(parse(&cmd, &["--json"]).is_ok());
+        assert!(parse(&cmd, &["--key", "a", "--cert", "b"]).is_ok());
+
+        let err = parse(&cmd, &["--json", "-t"]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::ArgumentConflict);
+        assert_eq!(err.get_args(), vec!["--json", "--table"]);
+        assert_eq!(
+            err.to_string(),
+            "The argument `--json` cannot be used together with `--table`"
+        );
+
+        let err = parse(&cmd, &["out.txt", "--table"]).unwrap_err();
+        assert_eq!(err.get_args(), vec!["[dest]", "--table"]);
+
+        let err = parse(&cmd, &["--key", "a"]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::MissingDependency);
+        assert_eq!(err.get_token(), Some("--key"));
+        assert_eq!(err.get_args(), vec!["--key", "--cert"]);
+    }
+
+    #[test]
+    fn test_arg_groups() {
+        let mut cmd = Command::new("fetch");
+        cmd.add_option(CmderOption::new("file").argument("<path>").group("source"))
+            .add_option(CmderOption::new("url").argument("<link>").group("source"))
+            .add_group(ArgGroup::new("source").policy(GroupPolicy::ExactlyOne))
+            .flag("--quiet", "No output")
+            .flag("--verbose", "Verbose output")
+            .add_group(ArgGroup::new("output").members(vec!["--quiet", "--verbose"]));
 
-            arg_vec.push(arg_cfg);
+        assert!(parse(&cmd, &["--file", "a.txt"]).is_ok());
+        assert!(parse(&cmd, &["--url", "x.com", "--quiet"]).is_ok());
+
+        let err = parse(&cmd, &[]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::ArgGroupViolation);
+        assert_eq!(err.get_args(), vec!["source", "--file", "--url"]);
+        assert_eq!(
+            err.to_string(),
+            "Expected exactly one of the following argument(s) for group `source`: `--file` `--url`"
+        );
+
+        let err = parse(&cmd, &["--file", "a", "--url", "b"]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::ArgGroupViolation);
+
+        let err = parse(&cmd, &["--file", "a", "--quiet", "--verbose"]).unwrap_err();
+        assert_eq!(err.get_args(), vec!["output", "--quiet", "--verbose"]);
+
+        // The help flag skips validation
+        assert!(parse(&cmd, &["--help"]).is_ok());
+    }
+
+    #[test]
+    fn test_conditional_requirements() {
+        let mut cmd = Command::new("login");
+        cmd.option("--auth <kind>", "The authentication kind")
+            .flag("--stdout", "Write to stdout")
+            .add_option(
+                CmderOption::new("token")
+                    .argument("<token>")
+                    .required_if("--auth", "bearer"),
+            )
+            .add_option(
+                CmderOption::new("output")
+                    .argument("<file>")
+                    .required_unless("--stdout"),
+            )
+            .add_argument(Argument::new("[user]").required_if("--auth", "basic"));
+
+        assert!(parse(&cmd, &["--stdout"]).is_ok());
+        assert!(parse(&cmd, &["--auth", "bearer", "--token", "x", "--output", "a"]).is_ok());
+
+        let err = parse(&cmd, &["--auth", "bearer", "--stdout"]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::MissingRequiredOption);
+        assert_eq!(err.get_args(), vec!["--token"]);
+        assert_eq!(err.get_help(), Some("required if `--auth` is `bearer`"));
+        assert_eq!(
+            err.to_string(),
+            "Missing the required option `--token`: required if `--auth` is `bearer`"
+        );
+
+        let err = parse(&cmd, &[]).unwrap_err();
+        assert_eq!(err.get_help(), Some("required unless `--stdout` is passed"));
+
+        let err = parse(&cmd, &["--auth=basic", "--stdout"]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::MissingRequiredArgument);
+        assert_eq!(err.get_args(), vec!["[user]"]);
+    }
+
+    #[test]
+    fn test_conditional_defaults() {
+        let mut cmd = Command::new("build");
+        cmd.flag("--release", "Build in release mode")
+            .add_option(
+                CmderOption::new("opt-level")
+                    .add_argument(Argument::new("<level>").default("0"))
+                    .default_if("--release", "true", "3"),
+            )
+            .add_argument(Argument::new("[target]").default_if("<level>", "3", "dist"));
+
+        let m = parse(&cmd, &[]).unwrap();
+        assert_eq!(m.get_option_arg("<level>"), Some("0".into()));
+        assert_eq!(m.get_arg("[target]"), None);
+
+        let m = parse(&cmd, &["--release"]).unwrap();
+        assert_eq!(m.get_option_arg("<level>"), Some("3".into()));
+        assert_eq!(m.get_value_forms("--opt-level"), vec![ValueForm::Default]);
+        assert!(!m.contains_option("--opt-level"));
+        assert_eq!(m.get_arg("[target]"), Some("dist".into()));
+
+        // Rules can point at option arguments by name as well
+        let m = parse(&cmd, &["--opt-level", "3"]).unwrap();
+        assert_eq!(m.get_arg("[target]"), Some("dist".into()));
+
+        let m = parse(&cmd, &["--release", "--opt-level", "2", "build"]).unwrap();
+        assert_eq!(m.get_option_arg("<level>"), Some("2".into()));
+        assert_eq!(m.get_arg("[target]"), Some("build".into()));
+    }
+
+    #[test]
+    fn test_optional_option_values() {
+        let mut cmd = Command::new("ls");
+        cmd.option("-c --color[=when]", "When to colorize output")
+            .add_option(
+                CmderOption::new("sort")
+                    .short('s')
+                    .add_argument(Argument::new("[key]").default("name")),
+            )
+            .argument("[dir]", "The directory to list");
+
+        let m = parse(&cmd, &["--color", "src"]).unwrap();
+        assert!(m.contains_option("--color"));
+        assert_eq!(m.get_option_value("--color"), Some(None));
+        assert_eq!(m.get_value_forms("--color"), vec![ValueForm::Omitted]);
+        assert_eq!(m.get_arg("[dir]"), Some("src".into()));
+
+        let m = parse(&cmd, &["--color=never", "-calways"]).unwrap();
+        assert_eq!(m.get_option_value("--color"), Some(Some("always".into())));
+        assert_eq!(
+            m.get_value_forms("--color"),
+            vec![ValueForm::Equals, ValueForm::Glued]
+        );
+
+        // The value is read from the last occurrence only
+        let m = parse(&cmd, &["--color=never", "--color"]).unwrap();
+        assert_eq!(m.get_option_value("--color"), Some(None));
+        let m = parse(&cmd, &["-s", "size", "-s"]).unwrap();
+        assert_eq!(m.get_option_value("--sort"), Some(Some("name".into())));
+
+        let m = parse(&cmd, &["-s"]).unwrap();
+        assert_eq!(m.get_option_value("--sort"), Some(Some("name".into())));
+        assert_eq!(m.get_option_value("--color"), None);
+        assert_eq!(m.get_arg("[dir]"), None);
+    }
+
+    #[test]
+    fn test_value_counts() {
+        let mut cmd = Command::new("draw");
+        cmd.option("--point <x> <y>", "A point to draw")
+            .add_option(CmderOption::new("color").argument("<rgb...>").num_values(3))
+            .add_argument(Argument::new("<files...>").min_values(1).max_values(3));
+
+        let m = parse(
+            &cmd,
+            &["a", "--point", "1", "2", "--color", "1", "2", "3", "b"],
+        )
+        .unwrap();
+        assert_eq!(m.get_values::<u8>("<rgb...>"), vec![1, 2, 3]);
+        assert_eq!(m.get_values::<String>("<files...>"), vec!["a", "b"]);
+
+        let err = parse(&cmd, &["a", "--point", "1"]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::WrongNumberOfValues);
+        assert_eq!(err.to_string(), "Expected 2 value(s) for `--point`, got 1");
+
+        let err = parse(&cmd, &["a", "--color", "1", "2", "--point", "1", "2"]).unwrap_err();
+        assert_eq!(err.get_args(), vec!["--color", "3", "2"]);
+
+        let err = parse(&cmd, &["a", "--point"]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::OptionMissingArgument);
+
+        let err = parse(&cmd, &["a", "b", "c", "d"]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::WrongNumberOfValues);
+        assert_eq!(
+            err.to_string(),
+            "Expected 1 to 3 value(s) for `<files...>`, got 4"
+        );
+
+        let mut cmd = Command::new("cp");
+        cmd.add_argument(Argument::new("<sources...>").min_values(2))
+            .add_option(
+                CmderOption::new("exclude")
+                    .argument("<glob...>")
+                    .max_values(2),
+            );
+
+        let err = parse(&cmd, &["a"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Expected at least 2 value(s) for `<sources...>`, got 1"
+        );
+
+        // Values past the maximum of an option are left for the positional args
+        let m = parse(&cmd, &["--exclude", "x", "y", "a", "b"]).unwrap();
+        assert_eq!(m.get_values::<String>("<glob...>"), vec!["x", "y"]);
+        assert_eq!(m.get_values::<String>("<sources...>"), vec!["a", "b"]);
+
+        // A maximum of 0 values takes nothing instead of panicking
+        let mut cmd = Command::new("none");
+        cmd.add_argument(Argument::new("[xs...]").num_values(0))
+            .add_option(CmderOption::new("x").argument("<v...>").num_values(0));
+
+        let m = parse(&cmd, &[]).unwrap();
+        assert_eq!(m.get_arg("[xs...]"), None);
+        assert_eq!(
+            parse(&cmd, &["a"]).unwrap_err().get_kind(),
+            Event::UnresolvedArgument
+        );
+        assert_eq!(
+            parse(&cmd, &["--x", "a"]).unwrap_err().get_kind(),
+            Event::UnresolvedArgument
+        );
+    }
+
+    #[test]
+    fn test_option_occurrences() {
+        let mut cmd = Command::new("run");
+        cmd.option("-m --mount <src> <dst>", "Mount a volume")
+            .option("-e --env <pairs...>", "Environment variables")
+            .argument("[image]", "The image to run");
+
+        let args = [
+            "-m",
+            "/a",
+            "/x",
+            "alpine",
+            "--mount=/b",
+            "/y",
+            "-e",
+            "A=1",
+            "B=2",
+            "-m",
+            "/c",
+            "/z",
+        ];
+        let m = parse(&cmd, &args).unwrap();
+
+        assert_eq!(
+            m.get_occurrences("--mount"),
+            vec![vec!["/a", "/x"], vec!["/b", "/y"], vec!["/c", "/z"]]
+        );
+        assert_eq!(m.get_occurrences("-e"), vec![vec!["A=1", "B=2"]]);
+        assert_eq!(m.get_occurrences("--missing"), Vec::<Vec<String>>::new());
+        assert_eq!(m.get_arg("[image]"), Some("alpine".into()));
+        assert_eq!(m.get_option_count("--mount"), 3);
+
+        // Occurrences of short-only options are kept apart
+        let mut cmd = Command::new("cc");
+        cmd.option("-x <lang>", "The language")
+            .option("-y <std>", "The standard");
+        for syntax in ["-D <define>", "-U <undefine>"] {
+            let opt = crate::parse::options::new_option(syntax, "", false);
+            cmd.add_option(opt.key_value(true).allow_duplicate_keys(false));
+        }
+
+        let m = parse(&cmd, &["-x", "1", "-y", "2", "-x", "3"]).unwrap();
+        assert_eq!(m.get_occurrences("-x"), vec![vec!["1"], vec!["3"]]);
+        assert_eq!(m.get_occurrences("-y"), vec![vec!["2"]]);
+        assert!(m.contains_option("-y"));
+
+        // Keys are only checked for duplicates within the same option
+        assert!(parse(&cmd, &["-D", "a=1", "-U", "a=2"]).is_ok());
+        let err = parse(&cmd, &["-D", "a=1", "-D", "a=2"]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::InvalidArgumentValue);
+    }
+
+    #[test]
+    fn test_value_delimiter() {
+        let mut cmd = Command::new("label");
+        cmd.add_option(
+            CmderOption::new("tags")
+                .short('t')
+                .argument("<tag>")
+                .value_delimiter(','),
+        )
+        .add_option(
+            CmderOption::new("ports")
+                .add_argument(Argument::new("<port>").validate_with(|v| {
+                    v.parse::<u16>()
+                        .map(|_| ())
+                        .map_err(|_| "not a valid port".into())
+                }))
+                .value_delimiter(':'),
+        )
+        .argument("[name]", "The name to label");
+
+        let m = parse(&cmd, &["--tags", "a,b\\,c", "x", "-t=d"]).unwrap();
+        assert_eq!(m.get_instances_of("<tag>"), vec!["a", "b,c", "d"]);
+        assert_eq!(
+            m.get_occurrences("--tags"),
+            vec![vec!["a", "b,c"], vec!["d"]]
+        );
+        assert_eq!(m.get_arg("[name]"), Some("x".into()));
+
+        let m = parse(&cmd, &["--ports=80:443"]).unwrap();
+        assert_eq!(m.get_values::<u16>("<port>"), vec![80, 443]);
+
+        let err = parse(&cmd, &["--ports", "80:http"]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::InvalidArgumentValue);
+        assert_eq!(err.get_token(), Some("http"));
+
+        // Options with several arguments fill each of them as usual
+        cmd.add_option(
+            CmderOption::new("range")
+                .argument("<a>")
+                .argument("<b>")
+                .value_delimiter(','),
+        );
+
+        let m = parse(&cmd, &["--range", "1,2", "3"]).unwrap();
+        assert_eq!(m.get_option_arg("<a>"), Some("1,2".into()));
+        assert_eq!(m.get_option_arg("<b>"), Some("3".into()));
+
+        let err = parse(&cmd, &["--range", "1,2"]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::WrongNumberOfValues);
+    }
+
+    #[test]
+    fn test_key_value_options() {
+        let mut cmd = Command::new("build");
+        cmd.add_option(
+            CmderOption::new("define")
+                .short('D')
+                .argument("<pair>")
+                .key_value(true),
+        )
+        .add_option(
+            CmderOption::new("set")
+                .argument("<pairs...>")
+                .key_value(true)
+                .allow_duplicate_keys(false),
+        );
+
+        let m = parse(&cmd, &["-D", "a=1", "-Db=x=y", "--define=a=2"]).unwrap();
+        let map = m.get_map("--define");
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["a"], "2");
+        assert_eq!(map["b"], "x=y");
+        assert!(m.get_map("--set").is_empty());
+
+        let m = parse(&cmd, &["--set", "a.b=c", "d="]).unwrap();
+        assert_eq!(m.get_map("--set")["d"], "");
+
+        let err = parse(&cmd, &["-D", "novalue"]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::InvalidArgumentValue);
+        assert_eq!(
+            err.to_string(),
+            "Invalid value `novalue` for argument `<pair>`: expected a key=value pair"
+        );
+        assert!(parse(&cmd, &["-D", "=1"]).is_err());
+
+        let err = parse(&cmd, &["--set", "a=1", "--set", "a=2"]).unwrap_err();
+        assert_eq!(err.get_token(), Some("a=2"));
+        assert_eq!(
+            err.get_args()[2],
+            "the key `a` has already been passed".to_string()
+        );
+    }
+
+    #[test]
+    fn test_hyphen_values() {
+        let mut cmd = Command::new("geo");
+        cmd.option("--offset <n>", "The offset to apply")
+            .flag("-v --verbose", "Verbose output")
+            .add_option(
+                CmderOption::new("exec")
+                    .add_argument(Argument::new("<cmd>").allow_hyphen_values(true)),
+            )
+            .add_argument(Argument::new("[input]"));
+
+        let m = parse(&cmd, &["-", "--exec", "-la"]).unwrap();
+        assert_eq!(m.get_arg("[input]"), Some("-".into()));
+        assert_eq!(m.get_option_arg("<cmd>"), Some("-la".into()));
+
+        // Defined flags still take precedence over hyphen values
+        let err = parse(&cmd, &["--exec", "-v"]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::OptionMissingArgument);
+
+        // Negative numbers are only accepted when the setting is enabled
+        let err = parse(&cmd, &["--offset", "-5"]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::OptionMissingArgument);
+        assert_eq!(
+            parse(&cmd, &["-3"]).unwrap_err().get_kind(),
+            Event::UnknownOption
+        );
+
+        cmd.set(Setting::AllowNegativeNumbers, true);
+
+        let m = parse(&cmd, &["--offset", "-5", "-2.5", "-v"]).unwrap();
+        assert_eq!(m.get_value::<i32>("<n>"), Some(-5));
+        assert_eq!(m.get_value::<f64>("[input]"), Some(-2.5));
+        assert!(m.contains_flag("-v"));
+
+        assert_eq!(
+            parse(&cmd, &["-x"]).unwrap_err().get_kind(),
+            Event::UnknownOption
+        );
+    }
+
+    #[test]
+    fn test_non_greedy_positionals() {
+        let mut cmd = Command::new("cp");
+        cmd.argument("<src...>", "The files to copy")
+            .argument("<dest>", "The destination");
+
+        let m = parse(&cmd, &["a", "-", "c", "out"]).unwrap();
+        assert_eq!(m.get_values::<String>("<src...>"), vec!["a", "-", "c"]);
+        assert_eq!(m.get_arg("<dest>"), Some("out".into()));
+        assert_eq!(m.get_arg_indices("<src...>"), vec![0, 1, 2]);
+        assert_eq!(m.get_arg_indices("<dest>"), vec![3]);
+
+        let err = parse(&cmd, &["a"]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::MissingRequiredArgument);
+        assert_eq!(err.get_args(), vec!["<dest>"]);
+
+        let mut cmd = Command::new("greet");
+        cmd.add_argument(Argument::new("[prefix]").default("Hello"))
+            .argument("<name>", "The name to greet")
+            .argument("[suffix]", "Appended to the greeting");
+
+        let m = parse(&cmd, &["Bob"]).unwrap();
+        assert_eq!(m.get_arg("[prefix]"), Some("Hello".into()));
+        assert_eq!(m.get_arg("<name>"), Some("Bob".into()));
+        assert!(m.get_arg_indices("[prefix]").is_empty());
+
+        let m = parse(&cmd, &["Hi", "Bob", "!"]).unwrap();
+        assert_eq!(m.get_arg("[prefix]"), Some("Hi".into()));
+        assert_eq!(m.get_arg("<name>"), Some("Bob".into()));
+        assert_eq!(m.get_arg("[suffix]"), Some("!".into()));
+    }
+
+    #[test]
+    fn test_trailing_values() {
+        let mut cmd = Command::new("rm");
+        cmd.flag("-f --force", "Ignore missing files")
+            .argument("<file>", "The file to remove");
+
+        let m = parse(&cmd, &["-f", "--", "-weird-file", "--force", "x"]).unwrap();
+        assert_eq!(m.get_arg("<file>"), Some("-weird-file".into()));
+        assert_eq!(m.get_positional_args(), vec!["--force", "x"]);
+        assert_eq!(m.get_arg_indices("<file>"), vec![2]);
+
+        let m = parse(&cmd, &["a", "--", "b"]).unwrap();
+        assert_eq!(m.get_arg("<file>"), Some("a".into()));
+        assert_eq!(m.get_positional_args(), vec!["b"]);
+
+        // Values after `--` are never reported as unresolved
+        let err = parse(&cmd, &["a", "b", "c", "--", "d"]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::UnresolvedArgument);
+        assert_eq!(err.get_args(), vec!["b", "c"]);
+
+        let mut cmd = Command::new("run");
+        cmd.argument("[args...]", "Arguments for the script");
+        cmd.set(Setting::PassThroughDoubleDash, true);
+
+        let m = parse(&cmd, &["a", "--", "b"]).unwrap();
+        assert_eq!(m.get_values::<String>("[args...]"), vec!["a", "--", "b"]);
+        assert!(m.get_positional_args().is_empty());
+    }
+
+    #[test]
+    fn test_abbreviations() {
+        let mut cmd = Command::new("docker");
+        cmd.flag("--verbose", "Verbose output")
+            .flag("--version", "Print the version")
+            .add_flag(CmderFlag::new("color").negatable(true))
+            .option("--level <n>", "The log level")
+            .subcommand("container")
+            .argument("[name]", "The container name");
+        cmd.subcommand("context");
+        cmd.subcommand("image");
+
+        // Abbreviations are opt-in
+        let err = parse(&cmd, &["--verb"]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::UnknownOption);
+
+        cmd.set(Setting::AllowAbbreviations, true);
+
+        let m = parse(&cmd, &["--verb", "--lev=3", "--no-col", "im"]).unwrap();
+        assert!(m.contains_flag("--verbose"));
+        assert_eq!(m.get_value::<u8>("<n>"), Some(3));
+        assert_eq!(m.get_flag_state("--color"), Some(false));
+        assert_eq!(m.get_matched_cmd().unwrap().get_name(), "image");
+
+        let m = parse(&cmd, &["conta", "cont"]).unwrap();
+        assert_eq!(m.get_matched_cmd().unwrap().get_name(), "container");
+        assert_eq!(m.get_arg("[name]"), Some("cont".into()));
+
+        let err = parse(&cmd, &["--ver"]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::AmbiguousArgument);
+        assert_eq!(err.get_suggestions(), &vec!["--verbose", "--version"]);
+        assert_eq!(
+            err.to_string(),
+            "The argument `--ver` is ambiguous, it could match: `--verbose` `--version`"
+        );
+
+        let err = parse(&cmd, &["con"]).unwrap_err();
+        assert_eq!(err.get_suggestions(), &vec!["container", "context"]);
+
+        // Flags never take attached values, whether abbreviated or not
+        for arg in ["--verbose=3", "--verb=3", "--no-col=x", "--no-color=x"] {
+            let err = parse(&cmd, &[arg]).unwrap_err();
+            assert_eq!(err.get_kind(), Event::UnknownOption);
+            assert_eq!(err.get_token(), Some(arg));
         }
+    }
+
+    #[test]
+    fn test_insensitive_matching() {
+        let mut cmd = Command::new("deploy");
+        cmd.flag("-n --dry-run", "Print the changes only")
+            .add_flag(CmderFlag::new("color").negatable(true))
+            .option("--log_level <level>", "The log level")
+            .add_argument(Argument::new("[env]").valid_values(vec!["staging", "prod"]))
+            .subcommand("container")
+            .alias("ctr")
+            .deprecated_alias("box", "");
+
+        assert_eq!(
+            parse(&cmd, &["--Dry-Run"]).unwrap_err().get_kind(),
+            Event::UnknownOption
+        );
 
-        Ok(arg_vec)
+        cmd.set(Setting::IgnoreCase, true);
+        cmd.set(Setting::IgnoreSeparators, true);
+
+        let m = parse(
+            &cmd,
+            &["--DRY_RUN", "--No-Color", "--log-level", "3", "Prod"],
+        )
+        .unwrap();
+        assert!(m.contains_flag("--dry-run"));
+        assert_eq!(m.get_flag_state("--color"), Some(false));
+        assert_eq!(m.get_option_arg("<level>"), Some("3".into()));
+        assert_eq!(m.get_arg("[env]"), Some("prod".into()));
+
+        let m = parse(&cmd, &["Container"]).unwrap();
+        assert_eq!(m.get_matched_cmd().unwrap().get_name(), "container");
+
+        // Aliases follow the same policy as names
+        let m = parse(&cmd, &["CTR"]).unwrap();
+        assert_eq!(m.get_matched_cmd().unwrap().get_name(), "container");
+        let m = parse(&cmd, &["Box"]).unwrap();
+        assert_eq!(m.get_matched_cmd().unwrap().get_name(), "container");
+        assert_eq!(m.get_deprecation_warnings().len(), 1);
+
+        // Short flags are still matched exactly
+        assert_eq!(
+            parse(&cmd, &["-N"]).unwrap_err().get_kind(),
+            Event::UnknownOption
+        );
+
+        let (leading, _) = cmd.get_flags()[1].generate(crate::Pattern::Legacy);
+        assert_eq!(leading, "-n, --dry-run");
+
+        // Settings set on a command apply to its subcommands, even when set after they were added
+        let mut program = Command::new("b");
+        program
+            .subcommand("run")
+            .flag("--dry-run", "Print the changes only");
+        program.set(Setting::IgnoreCase, true);
+
+        let m = parse(&program, &["RUN", "--DRY-RUN"]).unwrap();
+        assert_eq!(m.get_matched_cmd().unwrap().get_name(), "run");
+        assert!(m.contains_flag("--dry-run"));
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let mut cmd = Command::new("docker");
+        cmd.flag("-v --verbose", "Show more output")
+            .add_flag(CmderFlag::new("color").negatable(true))
+            .option("--log-level <level>", "The log level")
+            .subcommand("container");
+        cmd.subcommand("context");
+
+        let err = parse(&cmd, &["contaner"]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::UnknownCommand);
+        assert_eq!(err.get_suggestions(), &vec!["container"]);
+        assert_eq!(
+            err.get_message(),
+            "Could not find command: `contaner`, did you mean `container`?"
+        );
+
+        let err = parse(&cmd, &["--verbsoe"]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::UnknownOption);
+        assert_eq!(err.get_suggestions(), &vec!["--verbose"]);
+
+        let err = parse(&cmd, &["--no-colr"]).unwrap_err();
+        assert_eq!(err.get_suggestions(), &vec!["--no-color"]);
+
+        let err = parse(&cmd, &["--log-levl=3"]).unwrap_err();
+        assert_eq!(err.get_suggestions(), &vec!["--log-level"]);
+
+        // Nothing is suggested for short flags or values too far from any candidate
+        assert!(parse(&cmd, &["-x"])
+            .unwrap_err()
+            .get_suggestions()
+            .is_empty());
+        assert!(parse(&cmd, &["build"])
+            .unwrap_err()
+            .get_suggestions()
+            .is_empty());
+        assert_eq!(
+            parse(&cmd, &["build"]).unwrap_err().get_message(),
+            "Could not find command: `build`"
+        );
+
+        cmd.set(Setting::IgnoreCase, true);
+        let err = parse(&cmd, &["CONTEXTT"]).unwrap_err();
+        assert_eq!(err.get_suggestions(), &vec!["context"]);
     }
 
-    // fn resolve_options(&mut self, args: Vec<String>) {}
+    #[test]
+    fn test_global_items() {
+        let mut cmd = Command::new("docker");
+        cmd.add_flag(CmderFlag::new("verbose").short('v').global(true))
+            .add_option(
+                CmderOption::new("log-level")
+                    .add_argument(Argument::new("<level>").default("info"))
+                    .global(true),
+            )
+            .subcommand("image")
+            .flag("-f --force", "Force the removal")
+            .subcommand("prune")
+            .add_flag(CmderFlag::new("verbose").help("Shadows the global flag"));
+        cmd.subcommand("container")
+            .flag("-l --log-level", "Shadows by name");
+        cmd.propagate_globals();
+
+        let m = parse(&cmd, &["-v", "image", "--log-level", "debug", "-f"]).unwrap();
+        assert_eq!(m.get_matched_cmd().unwrap().get_name(), "image");
+        assert!(m.contains_flag("--verbose"));
+        assert_eq!(m.get_option_arg("<level>"), Some("debug".into()));
+
+        let m = parse(&cmd, &["--log-level", "warn", "image", "-vv"]).unwrap();
+        assert_eq!(m.get_flag_count("--verbose"), 2);
+        assert_eq!(m.get_option_arg("<level>"), Some("warn".into()));
+
+        // Defaults of global options apply at any depth
+        let m = parse(&cmd, &["image", "prune"]).unwrap();
+        assert_eq!(m.get_option_arg("<level>"), Some("info".into()));
+
+        // Items defined by a subcommand take precedence over global ones
+        let image = cmd.find_subcommand("image").unwrap();
+        let prune = image.find_subcommand("prune").unwrap();
+        let verbose = prune.get_flags().iter().find(|f| f.long == "--verbose");
+        assert!(!verbose.unwrap().is_global);
+        assert_eq!(
+            parse(&cmd, &["image", "prune", "-v"])
+                .unwrap_err()
+                .get_kind(),
+            Event::UnknownOption
+        );
+
+        let container = cmd.find_subcommand("container").unwrap();
+        assert!(container.get_options().is_empty());
+        assert!(parse(&cmd, &["container", "-l"]).is_ok());
+    }
+
+    #[test]
+    fn test_deprecated_aliases() {
+        let mut cmd = Command::new("pkg");
+        cmd.subcommand("install")
+            .alias("i")
+            .deprecated_alias("get", "");
+
+        let m = parse(&cmd, &["i"]).unwrap();
+        assert_eq!(m.get_matched_cmd().unwrap().get_name(), "install");
+        assert!(m.get_deprecation_warnings().is_empty());
+
+        let m = parse(&cmd, &["get"]).unwrap();
+        assert_eq!(m.get_matched_cmd().unwrap().get_name(), "install");
+        assert_eq!(
+            m.get_deprecation_warnings(),
+            vec!["The alias `get` is deprecated, use `install` instead"]
+        );
+    }
+
+    #[test]
+    fn test_help_after_option() {
+        let mut cmd = Command::new("server");
+        cmd.option("-p --port <n>", "The port to use")
+            .add_option(CmderOption::new("range").argument("<values>").num_values(2));
+
+        for args in [
+            &["-p", "--help"][..],
+            &["-p", "-h"],
+            &["--range", "1", "--help"],
+        ] {
+            let m = parse(&cmd, args).unwrap();
+            assert!(m.contains_flag("--help"));
+        }
+
+        let err = parse(&cmd, &["-p"]).unwrap_err();
+        assert_eq!(err.get_kind(), Event::OptionMissingArgument);
+    }
 }
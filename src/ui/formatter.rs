@@ -136,10 +136,10 @@ impl Formatter {
         self.add(Designation::Other, "\n");
     }
 
-    pub fn format<'a, L, T: 'a>(&mut self, args: L, ptrn: &Pattern)
+    pub fn format<'a, L, T>(&mut self, args: L, ptrn: &Pattern)
     where
         L: IntoIterator<Item = &'a T>,
-        T: FormatGenerator,
+        T: FormatGenerator + 'a,
     {
         let mut values = vec![];
 
@@ -170,10 +170,10 @@ impl Formatter {
         let mut string_buff = String::with_capacity(cap);
         string_buff.push_str(leading);
 
-        let mut diff = if cap > string_buff.bytes().count() {
-            cap - string_buff.bytes().count()
+        let mut diff = if cap > string_buff.len() {
+            cap - string_buff.len()
         } else {
-            self.padding = string_buff.bytes().count() + 5;
+            self.padding = string_buff.len() + 5;
             5
         };
 
@@ -39,7 +39,7 @@ impl Theme {
         values.insert(Error, errors);
         values.insert(Other, others);
 
-        Self { 0: values }
+        Self(values)
     }
 
     pub fn get(&self, designation: Designation) -> Color {
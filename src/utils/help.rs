@@ -1,4 +1,8 @@
-use crate::{core::Command, ui::formatter::Pattern, Designation, Formatter, Theme};
+use crate::{
+    core::{Command, Setting},
+    ui::formatter::{FormatGenerator, Pattern},
+    Designation, Formatter, Theme,
+};
 
 pub struct HelpWriter {}
 
@@ -7,9 +11,15 @@ impl<'help> HelpWriter {
         let mut fmter = Formatter::new(theme.to_owned());
 
         // Utility vars
-        let has_flags = !cmd.get_flags().is_empty();
+        let (global_flags, flags): (Vec<_>, Vec<_>) =
+            cmd.get_flags().iter().partition(|f| f.is_global);
+        let (global_options, options): (Vec<_>, Vec<_>) =
+            cmd.get_options().iter().partition(|o| o.is_global);
+
+        let has_flags = !flags.is_empty();
         let has_args = !cmd.get_arguments().is_empty();
-        let has_options = !cmd.get_options().is_empty();
+        let has_options = !options.is_empty();
+        let has_globals = !global_flags.is_empty() || !global_options.is_empty();
         let has_subcmds = !cmd.get_subcommands().is_empty();
         let has_info = !cmd.get_cmd_info().is_empty();
 
@@ -39,17 +49,33 @@ impl<'help> HelpWriter {
 
         if has_flags {
             fmter.section("FLAGS");
-            fmter.format(cmd.get_flags(), ptrn);
+            fmter.format(flags, ptrn);
         }
 
         if has_options {
             fmter.section("OPTIONS");
-            fmter.format(cmd.get_options(), ptrn);
+            fmter.format(options, ptrn);
+        }
+
+        if has_globals {
+            fmter.section("GLOBAL OPTIONS");
+            fmter.format(global_flags, ptrn);
+            fmter.format(global_options, ptrn);
         }
 
         if has_subcmds {
             fmter.section("SUB-COMMANDS");
-            fmter.format(cmd.get_subcommands(), ptrn);
+            let show_aliases = shows_aliases(cmd);
+            let entries: Vec<_> = cmd
+                .get_subcommands()
+                .iter()
+                .map(|sub_cmd| SubcommandEntry {
+                    cmd: sub_cmd,
+                    show_aliases,
+                })
+                .collect();
+
+            fmter.format(&entries, ptrn);
         }
 
         if has_info {
@@ -60,3 +86,68 @@ impl<'help> HelpWriter {
         fmter.print();
     }
 }
+
+// Checks whether the `ShowCommandAliases` setting is enabled on the command or on any of its parents, up to the program
+fn shows_aliases(cmd: &Command) -> bool {
+    cmd.get_inherited_setting(Setting::ShowCommandAliases)
+}
+
+// A subcommand listed in the SUB-COMMANDS section, along with its visible aliases when they are to be shown
+struct SubcommandEntry<'a> {
+    cmd: &'a Command<'a>,
+    show_aliases: bool,
+}
+
+impl<'a> FormatGenerator for SubcommandEntry<'a> {
+    fn generate(&self, ptrn: Pattern) -> (String, String) {
+        let custom = matches!(ptrn, Pattern::Custom(_));
+        let (leading, mut floating) = self.cmd.generate(ptrn);
+        let aliases = self.cmd.get_aliases();
+
+        // Custom patterns place aliases themselves through `{{alias}}`
+        if self.show_aliases && !custom && !aliases.is_empty() {
+            floating.push_str(&format!(" [aliases: {}]", aliases.join(", ")));
+        }
+
+        (leading, floating)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Program;
+
+    #[test]
+    fn test_subcommand_aliases() {
+        let mut program = Program::new();
+        program
+            .subcommand("install")
+            .description("Install a package")
+            .aliases(vec!["i", "add"])
+            .hidden_alias("isntall");
+
+        let install = program.find_subcommand("install").unwrap();
+        let entry = |show_aliases| SubcommandEntry {
+            cmd: install,
+            show_aliases,
+        };
+
+        assert_eq!(
+            entry(false).generate(Pattern::Legacy).1,
+            "Install a package"
+        );
+        assert_eq!(
+            entry(true).generate(Pattern::Legacy).1,
+            "Install a package [aliases: i, add]"
+        );
+
+        // The setting is read from the command whose help is shown or its parents, never copied into subcommands
+        assert!(!shows_aliases(&program));
+        program.set(Setting::ShowCommandAliases, true);
+        assert!(shows_aliases(&program));
+
+        let install = program.find_subcommand("install").unwrap();
+        assert!(!install.get_setting(Setting::ShowCommandAliases));
+    }
+}
@@ -1,6 +1,6 @@
 mod err_help;
 mod help;
-mod suggest_commands;
+mod suggest;
 
 pub use help::HelpWriter;
-pub use suggest_commands::suggest_cmd;
+pub(crate) use suggest::suggest;
@@ -0,0 +1,97 @@
+use crate::parse::matching::MatchPolicy;
+
+// Returns the candidates similar enough to the value passed to be suggested in its place, closest first. Candidates at the same distance keep the order in which they were given
+pub(crate) fn suggest<'a>(
+    val: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+    policy: MatchPolicy,
+) -> Vec<String> {
+    let val = policy.normalize(val);
+    let val = val.trim_start_matches('-');
+
+    let mut ranked: Vec<(usize, String)> = vec![];
+
+    for candidate in candidates {
+        let normalized = policy.normalize(candidate);
+        let normalized = normalized.trim_start_matches('-');
+        let distance = edit_distance(val, normalized);
+
+        // Allow roughly one edit for every three characters of the candidate
+        let max_distance = (normalized.chars().count() / 3).max(1);
+        if distance > 0 && distance <= max_distance && !ranked.iter().any(|(_, c)| c == candidate) {
+            ranked.push((distance, candidate.to_string()));
+        }
+    }
+
+    ranked.sort_by_key(|(distance, _)| *distance);
+    ranked.into_iter().map(|(_, c)| c).collect()
+}
+
+// Computes the Damerau-Levenshtein distance(optimal string alignment) between two values, i.e the number of insertions, deletions, substitutions and transpositions of adjacent characters needed to turn one into the other
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    let mut table = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in table.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in table[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+
+            let mut distance = (table[i - 1][j] + 1)
+                .min(table[i][j - 1] + 1)
+                .min(table[i - 1][j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(table[i - 2][j - 2] + 1);
+            }
+
+            table[i][j] = distance;
+        }
+    }
+
+    table[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("container", "container"), 0);
+        assert_eq!(edit_distance("contaner", "container"), 1);
+        assert_eq!(edit_distance("cotnainer", "container"), 1);
+        assert_eq!(edit_distance("", "run"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let cmds = ["image", "container", "context", "volume"];
+        let policy = MatchPolicy::default();
+
+        assert_eq!(suggest("contaner", cmds, policy), vec!["container"]);
+        assert_eq!(suggest("contex", cmds, policy), vec!["context"]);
+        assert_eq!(suggest("build", cmds, policy), Vec::<String>::new());
+        assert_eq!(suggest("image", cmds, policy), Vec::<String>::new());
+
+        let flags = ["--verbose", "--version", "--help"];
+        assert_eq!(suggest("--verison", flags, policy), vec!["--version"]);
+        assert_eq!(suggest("--vrebose", flags, policy), vec!["--verbose"]);
+
+        // Candidates at the same distance keep their order
+        assert_eq!(suggest("--versio", flags, policy), vec!["--version"]);
+        assert_eq!(
+            suggest("ab", ["ac", "ad", "a"], policy),
+            vec!["ac", "ad", "a"]
+        );
+    }
+}
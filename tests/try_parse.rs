@@ -0,0 +1,129 @@
+use cmder::{CmderFlag, Command, Event, Program};
+
+fn create_default_program() -> Command<'static> {
+    let mut program = Program::new();
+
+    program
+        .author("vndaba")
+        .version("0.1.0")
+        .description("A test for non-exiting parsing")
+        .bin_name("server");
+
+    program
+        .subcommand("start")
+        .argument("<address>", "The address to listen on")
+        .required_option("-p --port <port-number>", "The port to listen on")
+        .option("-d --detached", "Run the server in the background")
+        .description("Start the server")
+        .action(|_m| panic!("Actions should not be invoked by try_parse"));
+
+    program
+}
+
+#[test]
+fn test_try_parse_matches() {
+    let mut program = create_default_program();
+    let matches = program
+        .try_parse_from(vec!["server", "start", "localhost", "-p", "8080", "-d"])
+        .unwrap();
+
+    assert_eq!(matches.get_matched_cmd().unwrap().get_name(), "start");
+    assert_eq!(matches.get_arg("<address>"), Some("localhost".into()));
+    assert_eq!(matches.get_value::<u16>("<port-number>"), Some(8080));
+    assert!(matches.contains_flag("--detached"));
+}
+
+#[test]
+fn test_try_parse_errors() {
+    let mut program = create_default_program();
+    let res = program.try_parse_from(vec!["server", "start", "localhost"]);
+    assert_eq!(res.unwrap_err().get_kind(), Event::MissingRequiredOption);
+
+    let mut program = create_default_program();
+    let res = program.try_parse_from(vec!["server", "start", "-p", "80"]);
+    assert_eq!(res.unwrap_err().get_kind(), Event::MissingRequiredArgument);
+
+    let mut program = create_default_program();
+    let res = program.try_parse_from(vec!["server", "stop"]);
+    assert_eq!(res.unwrap_err().get_kind(), Event::UnknownCommand);
+
+    let mut program = create_default_program();
+    let res = program.try_parse_from(vec!["server", "start", "localhost", "-x"]);
+    assert_eq!(res.unwrap_err().get_kind(), Event::UnknownOption);
+}
+
+#[test]
+fn test_error_context() {
+    let mut program = create_default_program();
+    let err = program
+        .try_parse_from(vec![
+            "server",
+            "start",
+            "localhost",
+            "-p",
+            "80",
+            "--verbose",
+        ])
+        .unwrap_err();
+
+    assert_eq!(err.get_kind(), Event::UnknownOption);
+    assert_eq!(err.get_token(), Some("--verbose"));
+    assert_eq!(err.get_matched_cmd(), Some("server start"));
+    assert_eq!(err.get_exit_code(), 20);
+    assert_eq!(
+        err.to_string(),
+        "You have passed an unknown option: `--verbose`"
+    );
+
+    let program = create_default_program();
+    let cfg = err.clone().into_event_cfg(&program);
+
+    assert_eq!(cfg.get_event(), Event::UnknownOption);
+    assert_eq!(cfg.get_exit_code(), 20);
+    assert_eq!(cfg.get_error(), Some(&err));
+    assert_eq!(cfg.get_matched_cmd().unwrap().get_name(), "start");
+
+    let boxed: Box<dyn std::error::Error> = Box::new(err);
+    assert!(boxed.source().is_none());
+}
+
+#[test]
+fn test_global_flags() {
+    let mut program = create_default_program();
+    program.add_flag(CmderFlag::new("verbose").short('v').global(true));
+
+    let matches = program
+        .try_parse_from(vec!["server", "start", "localhost", "-p", "80", "-v"])
+        .unwrap();
+    assert!(matches.contains_flag("--verbose"));
+
+    let mut program = create_default_program();
+    program.add_flag(CmderFlag::new("verbose").short('v').global(true));
+
+    let matches = program
+        .try_parse_from(vec![
+            "server",
+            "--verbose",
+            "start",
+            "localhost",
+            "-p",
+            "80",
+        ])
+        .unwrap();
+    assert!(matches.contains_flag("--verbose"));
+}
+
+#[test]
+fn test_silent_value_conversion() {
+    let mut program = create_default_program();
+    let matches = program
+        .try_parse_from(vec!["server", "start", "localhost", "-p", "abc"])
+        .unwrap();
+
+    // Conversion errors are not emitted for matches returned by try_parse
+    assert_eq!(matches.get_value::<u16>("<port-number>"), None);
+    assert!(matches.get_values::<u16>("<port-number>").is_empty());
+
+    let err = matches.try_get_value::<u16>("<port-number>").unwrap_err();
+    assert_eq!(err.get_kind(), Event::InvalidArgumentValue);
+}